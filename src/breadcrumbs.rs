@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::humanize_file_name;
+use crate::links;
+use crate::nav::escape;
+
+// A page of the site: its source relative to the input directory, its destination relative to
+// the output directory and its title.
+#[derive(Clone, Copy)]
+pub struct Page<'a> {
+    pub source: &'a Path,
+    pub destination: &'a Path,
+    pub title: &'a str,
+}
+
+// The trail of every page, by destination relative to the output directory, for the
+// `{breadcrumbs}` placeholder.
+#[derive(Default)]
+pub struct Breadcrumbs {
+    trails: HashMap<PathBuf, String>,
+}
+
+fn is_index(source: &Path) -> bool {
+    source.file_stem().is_some_and(|stem| stem == "index")
+}
+
+impl Breadcrumbs {
+    // Every directory of a page's path links to its `index` page when it has one, named after
+    // that page's title. Others are named after the directory. The root is `Home` by default.
+    pub fn new(pages: &[Page]) -> Breadcrumbs {
+        let indexes: HashMap<&Path, &Page> = pages
+            .iter()
+            .filter(|page| is_index(page.source))
+            .map(|page| (page.source.parent().unwrap_or(Path::new("")), page))
+            .collect();
+
+        let trails = pages
+            .iter()
+            .map(|page| {
+                let trail = Breadcrumbs::trail(page, &indexes);
+                (page.destination.to_path_buf(), trail)
+            })
+            .collect();
+
+        Breadcrumbs { trails }
+    }
+
+    fn trail(page: &Page, indexes: &HashMap<&Path, &Page>) -> String {
+        let mut directories: Vec<&Path> = page.source.ancestors().skip(1).collect();
+        directories.reverse();
+
+        // An index page stands for its own directory.
+        if is_index(page.source) {
+            directories.pop();
+        }
+
+        let mut crumbs: Vec<String> = directories
+            .iter()
+            .map(|directory| match indexes.get(directory) {
+                Some(index) => format!(
+                    "<a href=\"{}\">{}</a>",
+                    links::relative_url(page.destination, index.destination),
+                    escape(index.title)
+                ),
+                None if directory.as_os_str().is_empty() => "<span>Home</span>".to_owned(),
+                None => format!("<span>{}</span>", escape(&humanize_file_name(directory))),
+            })
+            .collect();
+
+        crumbs.push(format!(
+            "<span aria-current=\"page\">{}</span>",
+            escape(page.title)
+        ));
+
+        format!("<nav class=\"breadcrumbs\">{}</nav>", crumbs.join(" / "))
+    }
+
+    // Empty for the pages which aren't sources of the site, such as the tag pages.
+    pub fn render(&self, destination: &Path) -> &str {
+        self.trails.get(destination).map_or("", String::as_str)
+    }
+}
@@ -0,0 +1,35 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+// Text files, which compress well. Images, fonts and archives are already compressed.
+const EXTENSIONS: &[&str] = &[
+    "css", "csv", "htm", "html", "js", "json", "map", "md", "mjs", "svg", "txt", "wasm", "xml",
+];
+
+pub fn is_compressible(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy().to_lowercase();
+        EXTENSIONS.contains(&extension.as_str())
+    })
+}
+
+// `page.html` to `page.html.gz`.
+pub fn gzip_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+// Writes the gzip of the file next to it, for the hosts serving pre-compressed files. `level`
+// goes from 0 (no compression) to 9 (the smallest file).
+pub fn write_gzip(path: &Path, level: u32) -> io::Result<()> {
+    let content = fs::read(path)?;
+
+    let mut encoder = GzEncoder::new(File::create(gzip_path(path))?, Compression::new(level));
+    encoder.write_all(&content)?;
+    encoder.finish()?.flush()
+}
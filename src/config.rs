@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::encoding::Encoding;
+use crate::template::Syntax;
+use crate::{Format, GenericError, Opt};
+
+pub const CONFIG_FILE: &str = "webmark.toml";
+
+// Build settings read from `webmark.toml`, using the same names as the command line flags.
+// Relative paths are resolved from the directory containing the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    input_directory: Option<PathBuf>,
+    output_directory: Option<PathBuf>,
+    jobs: Option<usize>,
+    watch: Option<bool>,
+    no_highlight: Option<bool>,
+    highlight_theme: Option<String>,
+    base_url: Option<String>,
+    feed_limit: Option<usize>,
+    title_level: Option<u8>,
+    excerpt_length: Option<usize>,
+    last_modified_format: Option<String>,
+    max_file_size: Option<u64>,
+    wrap: Option<usize>,
+    slug_separator: Option<char>,
+    slug_lowercase: Option<bool>,
+    encoding: Option<Encoding>,
+    open: Option<bool>,
+    json_ld: Option<bool>,
+    clean: Option<bool>,
+    force: Option<bool>,
+    copy_adjacent: Option<bool>,
+    exclude: Option<Vec<String>>,
+    follow_symlinks: Option<bool>,
+    minify: Option<bool>,
+    gfm: Option<bool>,
+    tables: Option<bool>,
+    strikethrough: Option<bool>,
+    autolink: Option<bool>,
+    #[serde(alias = "tasklists")]
+    tasklist: Option<bool>,
+    footnotes: Option<bool>,
+    emoji: Option<bool>,
+    description_lists: Option<bool>,
+    superscript: Option<bool>,
+    math: Option<bool>,
+    heading_anchors: Option<bool>,
+    heading_id_prefix: Option<String>,
+    placeholder_syntax: Option<Syntax>,
+    pretty_urls: Option<bool>,
+    robots: Option<bool>,
+    robots_disallow: Option<Vec<String>>,
+    no_search_index: Option<bool>,
+    check_links: Option<bool>,
+    deny_broken_links: Option<bool>,
+    external_assets: Option<PathBuf>,
+    manifest: Option<PathBuf>,
+    single_file: Option<PathBuf>,
+    compress: Option<bool>,
+    compression_level: Option<u32>,
+    static_dir: Option<PathBuf>,
+    assets_file: Option<PathBuf>,
+    template_dir: Option<PathBuf>,
+    header_file: Option<PathBuf>,
+    footer_file: Option<PathBuf>,
+    extensions: Option<Vec<String>>,
+    output_extension: Option<String>,
+    slugify: Option<bool>,
+    format: Option<Format>,
+    preserve_timestamps: Option<bool>,
+    warn_duplicates: Option<bool>,
+    strict: Option<bool>,
+    fingerprint: Option<bool>,
+    default_404: Option<bool>,
+    lazy_images: Option<bool>,
+    code_copy: Option<bool>,
+    lint_headings: Option<bool>,
+    strip_comments: Option<bool>,
+    sanitize: Option<bool>,
+    frontmatter_only: Option<bool>,
+}
+
+impl Config {
+    // Returns None when there is no configuration file.
+    pub fn load(path: &Path) -> Result<Option<Config>, GenericError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).map_err(|error| {
+            GenericError::new(format!(
+                "Could not read configuration '{}'. Error: {}",
+                path.display(),
+                error
+            ))
+        })?;
+
+        toml::from_str(&content).map(Some).map_err(|error| {
+            GenericError::new(format!(
+                "Invalid configuration '{}'. Error: {}",
+                path.display(),
+                error
+            ))
+        })
+    }
+
+    // Reads settings written as in `webmark.toml`, for `Opt::with_settings`.
+    pub fn parse(settings: &str) -> Result<Config, GenericError> {
+        toml::from_str(settings)
+            .map_err(|error| GenericError::new(format!("Invalid settings. Error: {}", error)))
+    }
+
+    // Values from the command line take precedence: a setting from the file is only used when
+    // the corresponding flag wasn't `given`, which is told by its argument name.
+    pub fn merge_into(self, parameters: &mut Opt, given: &dyn Fn(&str) -> bool, directory: &Path) {
+        if let (false, Some(input)) = (given("inputs"), self.input_directory) {
+            parameters.inputs = vec![directory.join(input)];
+        }
+
+        if let (false, Some(output)) = (given("output"), self.output_directory) {
+            parameters.output = directory.join(output);
+        }
+
+        if let (false, Some(theme)) = (given("highlight-theme"), self.highlight_theme) {
+            parameters.highlight_theme = theme;
+        }
+
+        if let (false, Some(limit)) = (given("feed-limit"), self.feed_limit) {
+            parameters.feed_limit = limit;
+        }
+
+        if let (false, Some(level)) = (given("title-level"), self.title_level) {
+            parameters.title_level = level;
+        }
+
+        if let (false, Some(length)) = (given("excerpt-length"), self.excerpt_length) {
+            parameters.excerpt_length = length;
+        }
+
+        if let (false, Some(separator)) = (given("slug-separator"), self.slug_separator) {
+            parameters.slug_separator = separator;
+        }
+
+        if let (false, Some(lowercase)) = (given("slug-lowercase"), self.slug_lowercase) {
+            parameters.slug_lowercase = lowercase;
+        }
+
+        if let (false, Some(encoding)) = (given("encoding"), self.encoding) {
+            parameters.encoding = encoding;
+        }
+
+        if let (false, Some(level)) = (given("compression-level"), self.compression_level) {
+            parameters.compression_level = level;
+        }
+
+        if let (false, Some(format)) = (given("last-modified-format"), self.last_modified_format) {
+            parameters.last_modified_format = format;
+        }
+
+        if let (false, Some(wrap)) = (given("wrap"), self.wrap) {
+            parameters.wrap = wrap;
+        }
+
+        if let (false, Some(external)) = (given("external-assets"), self.external_assets) {
+            parameters.external_assets = external;
+        }
+
+        if let (false, Some(static_dir)) = (given("static-dir"), self.static_dir) {
+            parameters.static_dir = static_dir;
+        }
+
+        if let (false, Some(template_dir)) = (given("template-dir"), self.template_dir) {
+            parameters.template_dir = Some(directory.join(template_dir));
+        }
+
+        if let (false, Some(header)) = (given("header-file"), self.header_file) {
+            parameters.header_file = directory.join(header);
+        }
+
+        if let (false, Some(footer)) = (given("footer-file"), self.footer_file) {
+            parameters.footer_file = directory.join(footer);
+        }
+
+        if let (false, Some(assets_file)) = (given("assets-file"), self.assets_file) {
+            parameters.assets_file = assets_file;
+        }
+
+        if let (false, Some(prefix)) = (given("heading-id-prefix"), self.heading_id_prefix) {
+            parameters.heading_id_prefix = prefix;
+        }
+
+        if let (false, Some(syntax)) = (given("placeholder-syntax"), self.placeholder_syntax) {
+            parameters.placeholder_syntax = syntax;
+        }
+
+        if let (false, Some(format)) = (given("format"), self.format) {
+            parameters.format = format;
+        }
+
+        if let (false, Some(extensions)) = (given("extensions"), self.extensions) {
+            parameters.extensions = extensions;
+        }
+
+        if let (false, Some(extension)) = (given("output-extension"), self.output_extension) {
+            parameters.output_extension = extension;
+        }
+
+        if let (false, Some(disallow)) = (given("robots-disallow"), self.robots_disallow) {
+            parameters.robots_disallow = disallow;
+        }
+
+        if let (false, Some(exclude)) = (given("exclude"), self.exclude) {
+            parameters.exclude = exclude;
+        }
+
+        parameters.jobs = parameters.jobs.or(self.jobs);
+        parameters.base_url = parameters.base_url.take().or(self.base_url);
+        parameters.manifest = parameters.manifest.take().or(self.manifest);
+        parameters.single_file = parameters.single_file.take().or(self.single_file);
+        parameters.max_file_size = parameters.max_file_size.or(self.max_file_size);
+
+        parameters.watch |= self.watch.unwrap_or(false);
+        parameters.no_highlight |= self.no_highlight.unwrap_or(false);
+        parameters.clean |= self.clean.unwrap_or(false);
+        parameters.force |= self.force.unwrap_or(false);
+        parameters.copy_adjacent |= self.copy_adjacent.unwrap_or(false);
+        parameters.follow_symlinks |= self.follow_symlinks.unwrap_or(false);
+        parameters.minify |= self.minify.unwrap_or(false);
+        parameters.gfm |= self.gfm.unwrap_or(false);
+        parameters.tables |= self.tables.unwrap_or(false);
+        parameters.strikethrough |= self.strikethrough.unwrap_or(false);
+        parameters.autolink |= self.autolink.unwrap_or(false);
+        parameters.tasklist |= self.tasklist.unwrap_or(false);
+        parameters.footnotes |= self.footnotes.unwrap_or(false);
+        parameters.emoji |= self.emoji.unwrap_or(false);
+        parameters.description_lists |= self.description_lists.unwrap_or(false);
+        parameters.superscript |= self.superscript.unwrap_or(false);
+        parameters.math |= self.math.unwrap_or(false);
+        parameters.heading_anchors |= self.heading_anchors.unwrap_or(false);
+        parameters.pretty_urls |= self.pretty_urls.unwrap_or(false);
+        parameters.slugify |= self.slugify.unwrap_or(false);
+        parameters.preserve_timestamps |= self.preserve_timestamps.unwrap_or(false);
+        parameters.warn_duplicates |= self.warn_duplicates.unwrap_or(false);
+        parameters.strict |= self.strict.unwrap_or(false);
+        parameters.fingerprint |= self.fingerprint.unwrap_or(false);
+        parameters.default_404 |= self.default_404.unwrap_or(false);
+        parameters.lazy_images |= self.lazy_images.unwrap_or(false);
+        parameters.code_copy |= self.code_copy.unwrap_or(false);
+        parameters.compress |= self.compress.unwrap_or(false);
+        parameters.lint_headings |= self.lint_headings.unwrap_or(false);
+        parameters.strip_comments |= self.strip_comments.unwrap_or(false);
+        parameters.sanitize |= self.sanitize.unwrap_or(false);
+        parameters.frontmatter_only |= self.frontmatter_only.unwrap_or(false);
+        parameters.open |= self.open.unwrap_or(false);
+        parameters.json_ld |= self.json_ld.unwrap_or(false);
+        parameters.robots |= self.robots.unwrap_or(false);
+        parameters.no_search_index |= self.no_search_index.unwrap_or(false);
+        parameters.check_links |= self.check_links.unwrap_or(false);
+        parameters.deny_broken_links |= self.deny_broken_links.unwrap_or(false);
+    }
+}
@@ -0,0 +1,64 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+const BOM: &str = "\u{feff}";
+
+// Encoding of the markdown files which aren't UTF-8, from --encoding.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Encoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "latin1")]
+    Latin1,
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(encoding: &str) -> Result<Encoding, String> {
+        match encoding {
+            "utf-8" => Ok(Encoding::Utf8),
+            "latin1" => Ok(Encoding::Latin1),
+            "windows-1252" => Ok(Encoding::Windows1252),
+            _ => Err(format!("Unknown encoding '{}'.", encoding)),
+        }
+    }
+}
+
+// The characters of the bytes 0x80 to 0x9F in windows-1252, which are control characters in
+// latin1. The 5 unassigned bytes are kept as those control characters.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+fn decode_byte(byte: u8, encoding: Encoding) -> char {
+    match (encoding, byte) {
+        (Encoding::Windows1252, 0x80..=0x9f) => WINDOWS_1252[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+// The text of a markdown file, without its UTF-8 byte order mark. The files which aren't valid
+// UTF-8 are read with `encoding`, None when it is UTF-8 as well.
+pub fn decode(bytes: Vec<u8>, encoding: Encoding) -> Option<String> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Some(match text.strip_prefix(BOM) {
+            Some(text) => text.to_owned(),
+            None => text,
+        }),
+        Err(_) if encoding == Encoding::Utf8 => None,
+        Err(error) => Some(
+            error
+                .as_bytes()
+                .iter()
+                .map(|byte| decode_byte(*byte, encoding))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::GenericError;
+
+// Patterns given with --exclude, matched against paths relative to their input directory using `/`
+// as separator. `*` and `?` never match a `/`, `**` matches any number of directories:
+//
+// - `notes.md` only excludes the file at the root of the input, `**/notes.md` excludes it anywhere.
+// - `drafts` or `drafts/**` excludes the `drafts` directory at the root and everything below it.
+// - `**/*.draft.md` excludes matching files in every directory.
+//
+// A directory is tested both as `drafts` and `drafts/`, an excluded directory isn't walked at all.
+pub struct Exclusions {
+    roots: Vec<PathBuf>,
+    patterns: Vec<Pattern>,
+}
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+impl Exclusions {
+    pub fn new(roots: &[PathBuf], patterns: &[String]) -> Result<Exclusions, GenericError> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|error| {
+                    GenericError::new(format!(
+                        "Invalid exclude pattern '{}'. Error: {}",
+                        pattern, error
+                    ))
+                })
+            })
+            .collect::<Result<Vec<Pattern>, GenericError>>()?;
+
+        Ok(Exclusions {
+            roots: roots.to_vec(),
+            patterns,
+        })
+    }
+
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let relative = match self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+        {
+            Some(relative) => relative,
+            None => return false,
+        };
+
+        let components: Vec<_> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+
+        // The walk never reaches a file of an excluded directory, but the watcher does.
+        (1..components.len()).any(|length| self.matches(&components[..length].join("/"), true))
+            || self.matches(&components.join("/"), is_dir)
+    }
+
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_with(relative, OPTIONS)
+                || (is_dir && pattern.matches_with(&format!("{}/", relative), OPTIONS))
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+pub const FRONTMATTER_FILE: &str = "frontmatter.json";
+
+#[derive(Serialize)]
+pub struct Entry {
+    // Relative to its input directory.
+    pub source: PathBuf,
+    // Relative to the output directory, or absolute with --base-url.
+    pub url: String,
+    pub title: String,
+    pub excerpt: String,
+    // Every value of the frontmatter, sorted by key.
+    pub metadata: BTreeMap<String, String>,
+}
+
+// Writes every entry as a JSON array, sorted by source.
+pub fn write(destination: &Path, entries: &mut [Entry]) -> io::Result<()> {
+    entries.sort_by(|left, right| left.source.cmp(&right.source));
+
+    let export = serde_json::to_string_pretty(entries)?;
+
+    fs::write(destination, export)
+}
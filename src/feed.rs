@@ -0,0 +1,70 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::sitemap::escape_xml;
+
+pub const FEED_FILE: &str = "feed.xml";
+
+pub struct Item {
+    pub title: String,
+    pub url: String,
+    pub date: DateTime<Utc>,
+    pub excerpt: String,
+}
+
+pub struct Channel<'a> {
+    pub title: &'a str,
+    pub url: &'a str,
+}
+
+// Writes an RSS 2.0 feed (https://www.rssboard.org/rss-specification) of the `limit` most recent items.
+pub fn write(
+    destination: &Path,
+    channel: &Channel,
+    items: &mut Vec<Item>,
+    limit: usize,
+) -> io::Result<()> {
+    items.sort_by_key(|item| Reverse(item.date));
+    items.truncate(limit);
+
+    let mut feed = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n  <channel>\n",
+    );
+
+    feed.push_str(&format!(
+        "    <title>{}</title>\n",
+        escape_xml(channel.title)
+    ));
+    feed.push_str(&format!("    <link>{}</link>\n", escape_xml(channel.url)));
+    feed.push_str(&format!(
+        "    <description>Latest pages of {}</description>\n",
+        escape_xml(channel.title)
+    ));
+
+    for item in items.iter() {
+        feed.push_str("    <item>\n");
+        feed.push_str(&format!(
+            "      <title>{}</title>\n",
+            escape_xml(&item.title)
+        ));
+        feed.push_str(&format!("      <link>{}</link>\n", escape_xml(&item.url)));
+        feed.push_str(&format!("      <guid>{}</guid>\n", escape_xml(&item.url)));
+        feed.push_str(&format!(
+            "      <pubDate>{}</pubDate>\n",
+            item.date.to_rfc2822()
+        ));
+        feed.push_str(&format!(
+            "      <description>{}</description>\n",
+            escape_xml(&item.excerpt)
+        ));
+        feed.push_str("    </item>\n");
+    }
+
+    feed.push_str("  </channel>\n</rss>\n");
+
+    fs::write(destination, feed)
+}
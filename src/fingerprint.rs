@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{links, manifest};
+
+// Assets meant to be cached, referenced from the pages. Others such as `favicon.ico`, `CNAME` or
+// the documents keep their name, something else than the pages may look for them.
+const EXTENSIONS: &[&str] = &[
+    "avif", "css", "gif", "jpeg", "jpg", "js", "mjs", "otf", "png", "svg", "ttf", "webp", "woff",
+    "woff2",
+];
+
+// Number of hexadecimal digits of the hash kept in the file names.
+const HASH_LENGTH: usize = 8;
+
+pub fn is_fingerprinted(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy().to_lowercase();
+        EXTENSIONS.contains(&extension.as_str())
+    })
+}
+
+// The hash of the content, as it appears in the file names.
+pub fn hash(content: &[u8]) -> String {
+    let mut hash = manifest::hash(content);
+    hash.truncate(HASH_LENGTH);
+    hash
+}
+
+// `app.css` to `app.0123abcd.css`.
+fn with_hash(name: &str, hash: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}.{}.{}", stem, hash, extension),
+        _ => format!("{}.{}", name, hash),
+    }
+}
+
+// Hashes of the fingerprinted assets, by path relative to the output directory.
+#[derive(Default)]
+pub struct Fingerprints {
+    hashes: HashMap<PathBuf, String>,
+}
+
+impl Fingerprints {
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn insert(&mut self, relative: PathBuf, hash: String) {
+        self.hashes.insert(relative, hash);
+    }
+
+    // `css/app.css` to `css/app.0123abcd.css`, when the file is fingerprinted.
+    pub fn path(&self, relative: &Path) -> Option<PathBuf> {
+        let hash = self.hashes.get(relative)?;
+        let name = relative.file_name()?.to_string_lossy();
+
+        Some(relative.with_file_name(with_hash(&name, hash)))
+    }
+
+    // The hash of the asset a url of the page `page` points to, both relative to the output
+    // directory. Root-relative urls start with the `base_path`, see `links::base_path`.
+    fn find(&self, page: &Path, path: &str, base_path: &str) -> Option<&String> {
+        if path.is_empty() || links::is_external(path) {
+            return None;
+        }
+
+        let relative = match path.strip_prefix('/') {
+            Some(_) => {
+                let path = path.strip_prefix(base_path)?.strip_prefix('/')?;
+                PathBuf::from(links::percent_decode(path))
+            }
+            // A directory isn't an asset, whatever the name of its index.
+            None => links::resolve_link(page, path, "")?,
+        };
+
+        self.hashes.get(&relative)
+    }
+
+    // Points the `href` and `src` attributes of a page to the fingerprinted assets, the page
+    // being at `page` relative to the output directory.
+    pub fn rewrite_html(&self, html: &str, page: &Path, base_path: &str) -> String {
+        links::rewrite_html_urls(html, |url| {
+            let path_end = url.find(['#', '?']).unwrap_or(url.len());
+            let (path, suffix) = url.split_at(path_end);
+            let hash = self.find(page, path, base_path)?;
+
+            Some(match path.rsplit_once('/') {
+                Some((directory, name)) => {
+                    format!("{}/{}{}", directory, with_hash(name, hash), suffix)
+                }
+                None => format!("{}{}", with_hash(path, hash), suffix),
+            })
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::Write;
+
+use comrak::create_formatter;
+use comrak::html::format_node_default;
+use comrak::nodes::NodeValue;
+
+use crate::images::Images;
+
+// What `Formatter` changes of the html written by comrak, which is left as it is otherwise.
+#[derive(Default)]
+pub struct Changes {
+    // With --lazy-images, where the images of the page are looked for.
+    pub images: Option<Images>,
+    // With --code-copy.
+    pub code_copy: bool,
+}
+
+create_formatter!(Formatter<Changes>, {
+    // Renders the images with `loading="lazy"`, and with their `width` and `height` when they
+    // are local files in a known format. The opening of the tag is left to comrak, the
+    // attributes being added when closing it.
+    NodeValue::Image(ref link) => |context, node, entering| {
+        if entering || context.user.images.is_none() {
+            return format_node_default(context, node, entering);
+        }
+
+        if !link.title.is_empty() {
+            context.write_str("\" title=\"")?;
+            context.escape(&link.title)?;
+        }
+
+        context.write_str("\" loading=\"lazy\"")?;
+
+        let dimensions = context
+            .user
+            .images
+            .as_ref()
+            .and_then(|images| images.dimensions(&link.url));
+        if let Some((width, height)) = dimensions {
+            write!(context, " width=\"{}\" height=\"{}\"", width, height)?;
+        }
+
+        context.write_str(" />")?;
+    },
+    // Wraps the code blocks in a `<div class="code-block">`, with the language of the block in
+    // `data-lang`, for a script to add a copy button. The math blocks aren't code.
+    NodeValue::CodeBlock(ref block) => |context, node, entering| {
+        let language = block.info.split_whitespace().next().unwrap_or("");
+        if !entering || !context.user.code_copy || language == "math" {
+            return format_node_default(context, node, entering);
+        }
+
+        context.cr()?;
+        context.write_str("<div class=\"code-block\"")?;
+        if !language.is_empty() {
+            context.write_str(" data-lang=\"")?;
+            context.escape(language)?;
+            context.write_str("\"")?;
+        }
+        context.write_str(">\n")?;
+
+        let rendering = format_node_default(context, node, entering)?;
+        context.write_str("</div>\n")?;
+        return Ok(rendering);
+    },
+});
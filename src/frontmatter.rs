@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_yaml::Value;
+
+const DELIMITER: &str = "---";
+
+// Splits a leading `---` delimited block from the rest of the document.
+// Returns the (yaml, markdown) pair, or None when the document doesn't start with frontmatter. A
+// first `---` line which is never closed is a markdown thematic break rather than frontmatter.
+fn split(content: &str) -> Option<(&str, &str)> {
+    let first_line_end = content.find('\n')?;
+
+    if content[..first_line_end].trim_end() != DELIMITER {
+        return None;
+    }
+
+    let body = &content[first_line_end + 1..];
+    let mut offset = 0;
+
+    for line in body.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            return Some((&body[..offset], &body[offset + line.len()..]));
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
+
+// The yaml parser counts the lines from the start of the frontmatter, which is the second line
+// of the file: `at line 2 column 6` becomes `at line 3 column 6`.
+fn shift_lines(message: &str) -> String {
+    let mut shifted = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(index) = rest.find("line ") {
+        let (before, after) = rest.split_at(index + "line ".len());
+        shifted.push_str(before);
+
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match after[..digits].parse::<usize>() {
+            Ok(line) => shifted.push_str(&(line + 1).to_string()),
+            Err(_) => shifted.push_str(&after[..digits]),
+        }
+
+        rest = &after[digits..];
+    }
+
+    shifted.push_str(rest);
+    shifted
+}
+
+// Sequences are flattened to a comma separated list so every key maps to a plain string.
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.to_owned()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Sequence(items) => Some(
+            items
+                .iter()
+                .filter_map(value_to_string)
+                .collect::<Vec<String>>()
+                .join(", "),
+        ),
+        _ => None,
+    }
+}
+
+// Separates the frontmatter metadata from the markdown content.
+// A document without frontmatter yields empty metadata and its content unchanged.
+pub fn extract(content: &str) -> Result<(HashMap<String, String>, &str), String> {
+    let mut metadata = HashMap::new();
+
+    let (yaml, markdown) = match split(content) {
+        Some(parts) => parts,
+        None => return Ok((metadata, content)),
+    };
+
+    let document: Value =
+        serde_yaml::from_str(yaml).map_err(|error| shift_lines(&error.to_string()))?;
+
+    match document {
+        Value::Mapping(mapping) => {
+            for (key, value) in &mapping {
+                if let (Some(key), Some(value)) = (value_to_string(key), value_to_string(value)) {
+                    metadata.insert(key, value);
+                }
+            }
+        }
+        Value::Null => {}
+        _ => return Err("Frontmatter must be a list of `key: value` pairs.".to_owned()),
+    }
+
+    Ok((metadata, markdown))
+}
+
+// Accepts the usual frontmatter date formats: RFC 3339, `YYYY-MM-DD[ HH:MM[:SS]]`.
+// Dates without an offset are considered UTC.
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    for format in &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc());
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use comrak::adapters::SyntaxHighlighterAdapter;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{append_highlighted_html_for_styled_line, IncludeBackground};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+// Server-side syntax highlighting of fenced code blocks.
+// Blocks without a language, or with one syntect doesn't know, are rendered exactly as without it.
+pub struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme: Theme,
+}
+
+impl Highlighter {
+    pub fn new(theme_name: &str) -> Result<Highlighter, String> {
+        let mut theme_set = ThemeSet::load_defaults();
+
+        match theme_set.themes.remove(theme_name) {
+            Some(theme) => Ok(Highlighter {
+                syntax_set: SyntaxSet::load_defaults_newlines(),
+                theme,
+            }),
+            None => Err(format!(
+                "Unknown highlight theme '{}'. Available themes: {}.",
+                theme_name,
+                theme_set
+                    .themes
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    fn highlight(&self, code: &str, syntax: &SyntaxReference) -> Result<String, syntect::Error> {
+        let mut highlighter = HighlightLines::new(syntax, &self.theme);
+        let mut output = String::new();
+
+        for line in LinesWithEndings::from(code) {
+            let regions = highlighter.highlight_line(line, &self.syntax_set)?;
+            append_highlighted_html_for_styled_line(&regions, IncludeBackground::No, &mut output)?;
+        }
+
+        Ok(output)
+    }
+}
+
+impl SyntaxHighlighterAdapter for Highlighter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn fmt::Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        let syntax = lang
+            .filter(|lang| !lang.is_empty())
+            .and_then(|lang| self.syntax_set.find_syntax_by_token(lang));
+
+        match syntax.map(|syntax| self.highlight(code, syntax)) {
+            Some(Ok(highlighted)) => output.write_str(&highlighted),
+            _ => comrak::html::escape(output, code),
+        }
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn fmt::Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        comrak::html::write_opening_tag(output, "pre", attributes)
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn fmt::Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        comrak::html::write_opening_tag(output, "code", attributes)
+    }
+}
@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use comrak::nodes::{AstNode, NodeValue};
+
+use crate::links;
+
+// The dimensions are in the first bytes of the supported formats, JPEG files aside which may
+// start with a large metadata segment.
+const HEADER_LENGTH: u64 = 256 * 1024;
+
+// Where the images of a page are looked for.
+#[derive(Default)]
+pub struct Images {
+    // Directory of the page's source, relative urls are resolved from it.
+    pub directory: PathBuf,
+    // Directories the root-relative urls are resolved from, the first one having the file wins.
+    pub roots: Vec<PathBuf>,
+    // See `links::Rewriting`, the urls are rewritten before being rendered.
+    pub base_path: String,
+    pub moved: bool,
+}
+
+impl Images {
+    // The local file an image url points to, when it exists.
+    fn resolve(&self, url: &str) -> Option<PathBuf> {
+        if links::is_external(url) {
+            return None;
+        }
+
+        let end = url.find(['#', '?']).unwrap_or(url.len());
+        let path = links::percent_decode(&url[..end]);
+
+        let file = match path.strip_prefix('/') {
+            Some(path) => {
+                let path = path
+                    .strip_prefix(self.base_path.trim_start_matches('/'))
+                    .map_or(path, |path| path.trim_start_matches('/'));
+
+                self.roots
+                    .iter()
+                    .map(|root| root.join(path))
+                    .find(|file| file.is_file())?
+            }
+            None if self.moved => self.directory.join(path.strip_prefix("../")?),
+            None => self.directory.join(path),
+        };
+
+        file.is_file().then_some(file)
+    }
+
+    pub fn dimensions(&self, url: &str) -> Option<(u32, u32)> {
+        let mut header = Vec::new();
+        File::open(self.resolve(url)?)
+            .and_then(|file| file.take(HEADER_LENGTH).read_to_end(&mut header))
+            .ok()?;
+
+        dimensions(&header)
+    }
+}
+
+// Urls of the local images of the document which don't point to an existing file. Remote and
+// `data:` images are skipped.
+pub fn find_missing<'a>(ast_root: &'a AstNode<'a>, images: &Images) -> Vec<String> {
+    ast_root
+        .descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::Image(ref image)
+                if !image.url.is_empty()
+                    && !links::is_external(&image.url)
+                    && images.resolve(&image.url).is_none() =>
+            {
+                Some(image.url.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn read_u16_be(bytes: &[u8], index: usize) -> Option<u32> {
+    let bytes = bytes.get(index..index + 2)?;
+    Some(u32::from(bytes[0]) << 8 | u32::from(bytes[1]))
+}
+
+fn read_u16_le(bytes: &[u8], index: usize) -> Option<u32> {
+    let bytes = bytes.get(index..index + 2)?;
+    Some(u32::from(bytes[1]) << 8 | u32::from(bytes[0]))
+}
+
+fn read_u24_le(bytes: &[u8], index: usize) -> Option<u32> {
+    let bytes = bytes.get(index..index + 3)?;
+    Some(u32::from(bytes[2]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[0]))
+}
+
+fn read_u32_be(bytes: &[u8], index: usize) -> Option<u32> {
+    let bytes = bytes.get(index..index + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Walks the segments up to the first "start of frame" one, which holds the dimensions.
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut index = 2;
+
+    loop {
+        if *bytes.get(index)? != 0xFF {
+            return None;
+        }
+
+        match *bytes.get(index + 1)? {
+            // Fill byte.
+            0xFF => index += 1,
+            // Segments without a length.
+            0x01 | 0xD0..=0xD8 => index += 2,
+            // Every SOF marker but DHT, JPG and DAC.
+            0xC0..=0xCF if ![0xC4, 0xC8, 0xCC].contains(&bytes[index + 1]) => {
+                return Some((
+                    read_u16_be(bytes, index + 7)?,
+                    read_u16_be(bytes, index + 5)?,
+                ));
+            }
+            _ => index += 2 + read_u16_be(bytes, index + 2)? as usize,
+        }
+    }
+}
+
+// The (width, height) of a PNG, GIF, JPEG or WebP image, from the beginning of its file.
+fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((read_u32_be(bytes, 16)?, read_u32_be(bytes, 20)?));
+    }
+
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some((read_u16_le(bytes, 6)?, read_u16_le(bytes, 8)?));
+    }
+
+    if bytes.starts_with(b"\xFF\xD8") {
+        return jpeg_dimensions(bytes);
+    }
+
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => Some((
+                read_u16_le(bytes, 26)? & 0x3FFF,
+                read_u16_le(bytes, 28)? & 0x3FFF,
+            )),
+            b"VP8L" => {
+                let bits = read_u24_le(bytes, 21)? | u32::from(*bytes.get(24)?) << 24;
+                Some((1 + (bits & 0x3FFF), 1 + ((bits >> 14) & 0x3FFF)))
+            }
+            b"VP8X" => Some((1 + read_u24_le(bytes, 24)?, 1 + read_u24_le(bytes, 27)?)),
+            _ => None,
+        };
+    }
+
+    None
+}
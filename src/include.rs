@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::{self, Encoding};
+
+// Includes within includes are followed up to this depth, which also stops include loops.
+const MAX_DEPTH: usize = 8;
+
+// The path of a `{{> path/to/partial.md}}` line.
+fn include_path(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("{{>")?
+        .strip_suffix("}}")
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+// `included` gets every file included, directly or not, until an include fails. The partials are
+// read as the pages, with `encoding` when they aren't UTF-8.
+fn expand(
+    markdown: &str,
+    root: &Path,
+    encoding: Encoding,
+    depth: usize,
+    included: &mut Vec<PathBuf>,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+
+    for line in markdown.split_inclusive('\n') {
+        if is_fence(line) {
+            in_code_block = !in_code_block;
+        }
+
+        let path = match include_path(line) {
+            Some(path) if !in_code_block => path,
+            _ => {
+                expanded.push_str(line);
+                continue;
+            }
+        };
+
+        if depth >= MAX_DEPTH {
+            return Err(format!(
+                "Too many nested includes while including '{}' (is a partial including itself?).",
+                path
+            ));
+        }
+
+        let file = root
+            .join(path)
+            .canonicalize()
+            .map_err(|error| format!("Could not include '{}'. Error: {}", path, error))?;
+
+        // An absolute path or `..` could read any file of the machine into the site.
+        if !file.starts_with(root) {
+            return Err(format!(
+                "Could not include '{}', it is outside the input directory '{}'.",
+                path,
+                root.display()
+            ));
+        }
+
+        let bytes = fs::read(&file)
+            .map_err(|error| format!("Could not include '{}'. Error: {}", path, error))?;
+        let partial = encoding::decode(bytes, encoding).ok_or_else(|| {
+            format!(
+                "Could not include '{}', it isn't valid UTF-8. The --encoding option reads the files in another encoding.",
+                path
+            )
+        })?;
+        included.push(file);
+
+        expanded.push_str(&expand(&partial, root, encoding, depth + 1, included)?);
+
+        if !expanded.ends_with('\n') {
+            expanded.push('\n');
+        }
+    }
+
+    Ok(expanded)
+}
+
+// Replaces every `{{> path/to/partial.md}}` line by the content of that file, relative to `root`.
+// Lines inside fenced code blocks are left as they are. A document without includes isn't copied.
+// The partials must be inside `root`, once the symbolic links are resolved.
+pub fn expand_includes<'a>(
+    markdown: &'a str,
+    root: &Path,
+    encoding: Encoding,
+) -> Result<Cow<'a, str>, String> {
+    if !markdown.contains("{{>") {
+        return Ok(Cow::Borrowed(markdown));
+    }
+
+    let root = root
+        .canonicalize()
+        .map_err(|error| format!("Could not read '{}'. Error: {}", root.display(), error))?;
+
+    expand(markdown, &root, encoding, 0, &mut Vec::new()).map(Cow::Owned)
+}
+
+// The canonical paths of the files `expand_includes` includes, directly or not, which a page
+// depends on. Those found before a failing include only, the failure is reported by
+// `expand_includes`.
+pub fn included_files(markdown: &str, root: &Path, encoding: Encoding) -> Vec<PathBuf> {
+    let mut included = Vec::new();
+
+    if let (true, Ok(root)) = (markdown.contains("{{>"), root.canonicalize()) {
+        let _ = expand(markdown, &root, encoding, 0, &mut included);
+    }
+
+    included.sort();
+    included.dedup();
+    included
+}
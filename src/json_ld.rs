@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Person<'a> {
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    name: &'a str,
+}
+
+// The schema.org `Article` of a page. Only `@context` and `@type` are required, the fields the
+// page doesn't have are left out.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Article<'a> {
+    #[serde(rename = "@context")]
+    context: &'static str,
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    headline: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<Person<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+}
+
+fn non_empty(text: &str) -> Option<&str> {
+    Some(text.trim()).filter(|text| !text.is_empty())
+}
+
+// The `<script type="application/ld+json">` describing a page, for `{json_ld}`. `url` is the
+// absolute url of the page, known with a --base-url only.
+pub fn render(
+    title: &str,
+    description: &str,
+    date: Option<DateTime<Utc>>,
+    author: Option<&str>,
+    url: Option<&str>,
+) -> String {
+    let article = Article {
+        context: "https://schema.org",
+        kind: "Article",
+        headline: non_empty(title),
+        description: non_empty(description),
+        date_published: date.map(|date| date.to_rfc3339()),
+        author: author.and_then(non_empty).map(|name| Person {
+            kind: "Person",
+            name,
+        }),
+        url,
+    };
+
+    // A `</script>` in a value would end the script early, `<` is escaped as in JavaScript.
+    let json = serde_json::to_string(&article)
+        .unwrap_or_default()
+        .replace('<', "\\u003c");
+
+    format!("<script type=\"application/ld+json\">{}</script>", json)
+}
@@ -0,0 +1,4105 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::TcpListener;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{self, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use std::{error, fs, process, thread, vec::Vec};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use comrak::adapters::SyntaxHighlighterAdapter;
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::options::Plugins;
+use comrak::{Arena, Options};
+use rayon::prelude::*;
+use serde::Deserialize;
+use structopt::clap::{ArgMatches, ErrorKind};
+use structopt::StructOpt;
+
+#[macro_use]
+mod log;
+
+use config::Config;
+use exclude::Exclusions;
+use fingerprint::Fingerprints;
+use highlight::Highlighter;
+use template::Template;
+
+pub use log::{Warning, WarningKind};
+
+mod breadcrumbs;
+mod compress;
+mod config;
+mod encoding;
+mod exclude;
+mod export;
+mod feed;
+mod fingerprint;
+mod formatter;
+mod frontmatter;
+mod highlight;
+mod images;
+mod include;
+mod json_ld;
+mod links;
+mod manifest;
+mod minify;
+mod nav;
+mod pager;
+mod robots;
+mod sanitize;
+mod search;
+mod serve;
+mod single;
+mod sitemap;
+mod slug;
+mod tags;
+mod template;
+mod toc;
+mod watch;
+
+// The settings of a build, from the command line (see `configure` for the library).
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(name = "rs-webmark", about = "A markdown-to-html website.")]
+pub struct Opt {
+    /// Input directory, can be repeated to build the site from several directories. The first one
+    /// holds the site's files (`assets.config`, `webmark.toml`, the --static-dir...)
+    #[structopt(
+        parse(from_os_str),
+        long = "input-directory",
+        default_value = ".",
+        number_of_values = 1
+    )]
+    inputs: Vec<PathBuf>,
+
+    // The first of the input directories, set by `normalize`.
+    #[structopt(skip)]
+    input: PathBuf,
+
+    /// Output directory
+    #[structopt(parse(from_os_str), long = "output-directory", default_value = "./out")]
+    output: PathBuf,
+
+    /// Number of worker threads used to convert files (defaults to the number of logical CPUs)
+    #[structopt(short = "j", long = "jobs")]
+    jobs: Option<usize>,
+
+    /// Keep running after the build and rebuild whenever the content or templates change
+    #[structopt(long = "watch")]
+    watch: bool,
+
+    /// Don't highlight fenced code blocks, leaving it to a client-side highlighter
+    #[structopt(long = "no-highlight")]
+    no_highlight: bool,
+
+    /// Theme used to highlight fenced code blocks
+    #[structopt(long = "highlight-theme", default_value = "InspiredGitHub")]
+    highlight_theme: String,
+
+    /// Absolute url the site is published at (e.g. `https://example.com/`), required for the sitemap.
+    /// Root-relative links (`/style.css`) are prefixed with its path
+    #[structopt(long = "base-url")]
+    base_url: Option<String>,
+
+    /// Maximum number of pages listed in the feed
+    #[structopt(long = "feed-limit", default_value = "20")]
+    feed_limit: usize,
+
+    /// Level of the heading giving its title to a page, for sites using the level 1 headings for
+    /// something else
+    #[structopt(long = "title-level", default_value = "1", possible_values = &["1", "2", "3", "4", "5", "6"])]
+    title_level: u8,
+
+    /// Maximum number of characters of the excerpts, longer ones are cut and end with an ellipsis
+    #[structopt(long = "excerpt-length", default_value = "200")]
+    excerpt_length: usize,
+
+    /// Format of `{last_modified}`, the modification time of a page's source, such as `%d/%m/%Y`.
+    /// See https://docs.rs/chrono/latest/chrono/format/strftime/
+    #[structopt(long = "last-modified-format", default_value = "%Y-%m-%d")]
+    last_modified_format: String,
+
+    /// Skip the markdown files larger than this many bytes, with a warning. There is no limit by
+    /// default
+    #[structopt(long = "max-file-size")]
+    max_file_size: Option<u64>,
+
+    /// Column the lines written with `--format commonmark` are wrapped at, 0 doesn't wrap them.
+    /// The html isn't wrapped
+    #[structopt(long = "wrap", default_value = "0")]
+    wrap: usize,
+
+    /// Remove the content of the output directory before building
+    #[structopt(long = "clean")]
+    clean: bool,
+
+    /// Rebuild every page, even those whose output is newer than their sources
+    #[structopt(long = "force")]
+    force: bool,
+
+    /// Copy every non-markdown file of the input directory (images, PDFs, ...) to the output
+    #[structopt(long = "copy-adjacent")]
+    copy_adjacent: bool,
+
+    /// Skip the files and directories matching this glob, relative to the input directory
+    /// (e.g. `drafts/**` or `**/*.draft.md`), can be repeated
+    #[structopt(long = "exclude", number_of_values = 1)]
+    exclude: Vec<String>,
+
+    /// Extensions of the markdown files, compared case insensitively
+    #[structopt(
+        long = "extensions",
+        use_delimiter = true,
+        default_value = "md,markdown,mdown"
+    )]
+    extensions: Vec<String>,
+
+    /// Extension of the generated pages, such as `htm`. They have none when it is empty. The 404
+    /// page is always `404.html`
+    #[structopt(long = "output-extension", default_value = "html")]
+    output_extension: String,
+
+    /// Walk through symlinked directories, which are skipped otherwise
+    #[structopt(long = "follow-symlinks")]
+    follow_symlinks: bool,
+
+    /// Remove comments and redundant whitespace from the generated pages
+    #[structopt(long = "minify")]
+    minify: bool,
+
+    /// Enable the GitHub Flavored Markdown extensions: tables, strikethrough, autolinks and task lists
+    #[structopt(long = "gfm")]
+    gfm: bool,
+
+    /// Enable tables
+    #[structopt(long = "tables")]
+    tables: bool,
+
+    /// Enable ~~strikethrough~~
+    #[structopt(long = "strikethrough")]
+    strikethrough: bool,
+
+    /// Turn bare urls and `www.` addresses into links
+    #[structopt(long = "autolink")]
+    autolink: bool,
+
+    /// Render `- [ ]` and `- [x]` task list items as disabled checkboxes, with the
+    /// `contains-task-list`, `task-list-item` and `task-list-item-checkbox` classes
+    #[structopt(long = "tasklist", alias = "tasklists")]
+    tasklist: bool,
+
+    /// Enable footnotes
+    #[structopt(long = "footnotes")]
+    footnotes: bool,
+
+    /// Enable description lists: a term on its own line, followed by `: definition` lines
+    #[structopt(long = "description-lists")]
+    description_lists: bool,
+
+    /// Enable ^superscript^
+    #[structopt(long = "superscript")]
+    superscript: bool,
+
+    /// Turn `:rocket:` shortcodes into their emoji, unknown ones are kept as written
+    #[structopt(long = "emoji")]
+    emoji: bool,
+
+    /// Parse `$...$` and `$$...$$` as math, written as `data-math-style` elements for KaTeX or
+    /// MathJax to render in the browser
+    #[structopt(long = "math")]
+    math: bool,
+
+    /// Add a clickable `#` link to every heading, pointing to the heading itself
+    #[structopt(long = "heading-anchors")]
+    heading_anchors: bool,
+
+    /// Text prepended to every heading id (e.g. `h-` gives `h-introduction`)
+    #[structopt(long = "heading-id-prefix", default_value = "")]
+    heading_id_prefix: String,
+
+    /// How placeholders are written in the templates, `name` standing for their name (e.g. `{{name}}`)
+    #[structopt(long = "placeholder-syntax", default_value = "{name}")]
+    placeholder_syntax: template::Syntax,
+
+    /// Directory of the input copied as it is to the root of the output, `static/css/app.css`
+    /// becoming `css/app.css`
+    #[structopt(parse(from_os_str), long = "static-dir", default_value = "static")]
+    static_dir: PathBuf,
+
+    /// Directory the header, the footer and the `layouts` directory are read from, the first
+    /// input directory by default
+    #[structopt(parse(from_os_str), long = "template-dir")]
+    template_dir: Option<PathBuf>,
+
+    /// Directory of the layouts, set from the template directory
+    #[structopt(skip)]
+    layouts: PathBuf,
+
+    // The `head.html` of the template directory, for `{head_includes}`.
+    #[structopt(skip)]
+    head_file: PathBuf,
+
+    // Set once `normalize` resolved the paths, which `build` does for settings which aren't.
+    #[structopt(skip)]
+    normalized: bool,
+
+    // Set by `normalize` when --header-file or --footer-file is given, on the command line, in the
+    // configuration file or by the library, rather than left to its default.
+    #[structopt(skip)]
+    header_given: bool,
+    #[structopt(skip)]
+    footer_given: bool,
+
+    /// Template written before the content of every page, a default one is used when it doesn't
+    /// exist
+    #[structopt(
+        parse(from_os_str),
+        long = "header-file",
+        default_value = "header.html"
+    )]
+    header_file: PathBuf,
+
+    /// Template written after the content of every page, a default one is used when it doesn't
+    /// exist
+    #[structopt(
+        parse(from_os_str),
+        long = "footer-file",
+        default_value = "footer.html"
+    )]
+    footer_file: PathBuf,
+
+    /// File of the input listing the assets to copy, one path or glob pattern per line
+    #[structopt(
+        parse(from_os_str),
+        long = "assets-file",
+        default_value = "assets.config"
+    )]
+    assets_file: PathBuf,
+
+    // Set by `normalize` when --assets-file is given, a missing default file
+    // simply meaning there are no listed assets.
+    #[structopt(skip)]
+    assets_given: bool,
+
+    /// Directory of the output where the assets from outside the input directory are copied
+    #[structopt(parse(from_os_str), long = "external-assets", default_value = "assets")]
+    external_assets: PathBuf,
+
+    /// Show what would be written, copied or removed, without touching the output directory
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// Report the relative links which don't point to a page or an asset of the site
+    #[structopt(long = "check-links")]
+    check_links: bool,
+
+    /// Like --check-links, and fail the build when a link is broken
+    #[structopt(long = "deny-broken-links")]
+    deny_broken_links: bool,
+
+    /// Add `loading="lazy"` to the images, with their `width` and `height` when they are local
+    /// PNG, GIF, JPEG or WebP files
+    #[structopt(long = "lazy-images")]
+    lazy_images: bool,
+
+    /// Remove the html comments of the pages, but the `<!-- more -->` markers
+    #[structopt(long = "strip-comments")]
+    strip_comments: bool,
+
+    /// Remove the html tags and attributes other than those of a documentation from the pages, for
+    /// content which can't be trusted
+    #[structopt(long = "sanitize")]
+    sanitize: bool,
+
+    /// Warn about the headings skipping a level, as a level 3 heading right after a level 1 one
+    #[structopt(long = "lint-headings")]
+    lint_headings: bool,
+
+    /// Wrap the code blocks in a `<div class="code-block">`, with their language in `data-lang`,
+    /// for a script to add a copy button
+    #[structopt(long = "code-copy")]
+    code_copy: bool,
+
+    /// Write a minimal `404.html` page when the input has no `404.md`
+    #[structopt(long = "default-404")]
+    default_404: bool,
+
+    /// Add a hash of their content to the name of the copied styles, scripts, images and fonts,
+    /// such as `app.0123abcd.css`, and point the pages to these names. Every page is rebuilt
+    #[structopt(long = "fingerprint")]
+    fingerprint: bool,
+
+    /// Fail the build on any warning, and stop it before writing anything when a page can't be
+    /// read, such as one with an invalid frontmatter, rather than skipping it
+    #[structopt(long = "strict")]
+    strict: bool,
+
+    /// Only warn when several sources are written to the same file, rather than failing the build.
+    /// The first source in path order is written either way
+    #[structopt(long = "warn-duplicates")]
+    warn_duplicates: bool,
+
+    /// Give the copied assets the modification time of their source
+    #[structopt(long = "preserve-timestamps")]
+    preserve_timestamps: bool,
+
+    /// Write a JSON file listing every file written: the pages, the assets, the site-wide files
+    /// and the gzip siblings, with their source, size and hash. Relative to the output directory
+    #[structopt(parse(from_os_str), long = "manifest")]
+    manifest: Option<PathBuf>,
+
+    /// Only write `frontmatter.json` to the output directory, the frontmatter, title and excerpt
+    /// of every page as a JSON array, for the listings built by another tool
+    #[structopt(long = "frontmatter-only")]
+    frontmatter_only: bool,
+
+    /// Also write a gzip of every page and text asset next to it, as `page.html.gz`, for the
+    /// hosts serving pre-compressed files
+    #[structopt(long = "compress")]
+    compress: bool,
+
+    /// Compression level of --compress, from 0 (fastest) to 9 (smallest)
+    #[structopt(long = "compression-level", default_value = "9")]
+    compression_level: u32,
+
+    /// Write every page to this single html file, relative to the output directory, rather than
+    /// one file per page. The header and the footer are written once
+    #[structopt(parse(from_os_str), long = "single-file")]
+    single_file: Option<PathBuf>,
+
+    /// Write a `robots.txt` allowing every crawler, pointing to the sitemap when there's a
+    /// --base-url
+    #[structopt(long = "robots")]
+    robots: bool,
+
+    /// Path the `robots.txt` disallows, such as `/drafts/`. Can be repeated, implies --robots
+    #[structopt(long = "robots-disallow", number_of_values = 1)]
+    robots_disallow: Vec<String>,
+
+    /// Don't write the `search-index.json` file listing the text of every page
+    #[structopt(long = "no-search-index")]
+    no_search_index: bool,
+
+    /// Turn the names of the pages and their directories into lowercase, dash separated slugs
+    #[structopt(long = "slugify")]
+    slugify: bool,
+
+    /// Character separating the words of the slugs, of --slugify and of the heading ids
+    #[structopt(long = "slug-separator", default_value = "-", possible_values = &["-", "_"])]
+    slug_separator: char,
+
+    /// Whether the slugs, of --slugify and of the heading ids, are lowercased
+    #[structopt(
+        long = "slug-lowercase",
+        default_value = "true",
+        possible_values = &["true", "false"],
+        parse(try_from_str)
+    )]
+    slug_lowercase: bool,
+
+    /// Write `page.md` to `page/index.html` so that it is served from `page/`
+    #[structopt(long = "pretty-urls")]
+    pretty_urls: bool,
+
+    /// Serve the output directory on localhost after building, on the given port (8000 by default)
+    #[structopt(long = "serve")]
+    serve: Option<Option<u16>>,
+
+    /// Fill `{json_ld}` with a JSON-LD script describing the page as an article, from its title,
+    /// description, date and author, and its url with --base-url
+    #[structopt(long = "json-ld")]
+    json_ld: bool,
+
+    /// Open the served site in the default browser once it is built, with --serve
+    #[structopt(long = "open")]
+    open: bool,
+
+    /// Also show the details of every processed file
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    verbose: u64,
+
+    /// Only show errors
+    #[structopt(short = "q", long = "quiet")]
+    quiet: bool,
+
+    /// Encoding of the markdown files which aren't valid UTF-8: `utf-8` skips them, `latin1` or
+    /// `windows-1252` reads them in that encoding
+    #[structopt(long = "encoding", default_value = "utf-8", possible_values = &["utf-8", "latin1", "windows-1252"])]
+    encoding: encoding::Encoding,
+
+    /// What is written for every page: `html` (the page in the templates), `fragment` (the page's
+    /// html alone) or `commonmark` (the markdown source, normalized)
+    #[structopt(long = "format", default_value = "html", possible_values = &["html", "fragment", "commonmark"])]
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    Html,
+    Fragment,
+    Commonmark,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format {
+            "html" => Ok(Format::Html),
+            "fragment" => Ok(Format::Fragment),
+            "commonmark" => Ok(Format::Commonmark),
+            _ => Err(format!("Unknown format '{}'.", format)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GenericError {
+    message: String,
+}
+
+impl GenericError {
+    fn new(error: String) -> GenericError {
+        GenericError { message: error }
+    }
+}
+
+impl std::fmt::Display for GenericError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[error] {}", self.message)
+    }
+}
+
+impl error::Error for GenericError {
+    fn description(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+#[derive(Default)]
+struct FileData {
+    html_content: String,
+    title: String,
+    toc: String,
+    // Frontmatter `description`, or the plain text of the page's summary.
+    excerpt: String,
+    metadata: HashMap<String, String>,
+    // Words of the text, code blocks excluded.
+    word_count: usize,
+    // Estimated minutes to read the page.
+    reading_time: usize,
+    // Plain text of the whole page, for the search index.
+    text: String,
+    // Relative urls of the page's links, once rewritten, for --check-links.
+    links: Vec<String>,
+    // Canonical paths of the partials the page includes, which it is rebuilt for.
+    includes: Vec<PathBuf>,
+}
+
+// Extensions are compared case insensitively, `extensions` being lowercase.
+fn is_markdown_file(extensions: &[String], path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy().to_lowercase();
+        extensions.contains(&extension)
+    })
+}
+
+// Markdown files above --max-file-size aren't read, so that a large file committed by accident
+// doesn't stall the build. As any warning, this fails the build with --strict.
+fn is_too_large(max_file_size: Option<u64>, path: &Path) -> bool {
+    let (Some(limit), Ok(metadata)) = (max_file_size, fs::metadata(path)) else {
+        return false;
+    };
+
+    if metadata.len() <= limit {
+        return false;
+    }
+
+    warn!(
+        kind = WarningKind::Skipped, source = path;
+        "Skipping '{}', its {} bytes exceed the --max-file-size of {} bytes.",
+        path.display(),
+        metadata.len(),
+        limit
+    );
+    true
+}
+
+// State of a walk through the input directory.
+struct Walk<'a> {
+    exclusions: &'a Exclusions,
+    extensions: &'a [String],
+    // Never walked through, it may be inside the input directory.
+    output: &'a Path,
+    // Copied as it is rather than converted, see `list_static_files`.
+    static_dir: &'a Path,
+    follow_symlinks: bool,
+    max_file_size: Option<u64>,
+    // Canonical paths of the directories walked so far, a symlink pointing back to one
+    // of them would otherwise make the walk recurse forever.
+    visited: HashSet<PathBuf>,
+    // Other regular files met during the walk, for --copy-adjacent.
+    others: Vec<PathBuf>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(arguments: &'a Opt, exclusions: &'a Exclusions) -> Walk<'a> {
+        Walk {
+            exclusions,
+            extensions: &arguments.extensions,
+            output: &arguments.output,
+            static_dir: &arguments.static_dir,
+            follow_symlinks: arguments.follow_symlinks,
+            max_file_size: arguments.max_file_size,
+            visited: HashSet::new(),
+            others: Vec::new(),
+        }
+    }
+}
+
+// Sorts paths case insensitively, so that files are processed in the same order on every
+// machine whatever the file system lists first. Paths differing only by their case (`a.md` and
+// `A.md`) are then sorted by their bytes, uppercase first.
+fn compare_paths(left: &Path, right: &Path) -> Ordering {
+    let lowercase = |path: &Path| path.to_string_lossy().to_lowercase();
+
+    lowercase(left)
+        .cmp(&lowercase(right))
+        .then_with(|| left.cmp(right))
+}
+
+// Every file of the --static-dir, markdown files included, as the regular files of a walk through
+// it that doesn't look for pages.
+fn list_static_files(arguments: &Opt, exclusions: &Exclusions) -> Vec<PathBuf> {
+    if !arguments.static_dir.is_dir() {
+        return Vec::new();
+    }
+
+    let mut walk = Walk {
+        extensions: &[],
+        ..Walk::new(arguments, exclusions)
+    };
+    list_markdown_files(&arguments.static_dir, &mut walk);
+
+    walk.others
+        .sort_by(|left, right| compare_paths(left, right));
+    walk.others
+}
+
+fn list_markdown_files(path: &Path, walk: &mut Walk) -> Vec<PathBuf> {
+    let mut files = Vec::<PathBuf>::new();
+
+    match path.canonicalize() {
+        Ok(canonical) => {
+            if !walk.visited.insert(canonical) {
+                warn!(
+                    kind = WarningKind::Skipped, source = path;
+                    "Skipping '{}', it leads to a directory already walked through.",
+                    path.display()
+                );
+                return files;
+            }
+        }
+        Err(error) => {
+            warn!(
+                kind = WarningKind::Skipped, source = path;
+                "Skipping '{}', could not resolve its path. Error: {}",
+                path.display(),
+                error
+            );
+            return files;
+        }
+    }
+
+    let dir_entries = fs::read_dir(path);
+
+    match dir_entries {
+        Ok(dir) => {
+            for entry in dir {
+                match entry {
+                    Ok(entry) => {
+                        let mut file_type = match entry.file_type() {
+                            Ok(file_type) => file_type,
+                            Err(error) => {
+                                warn!(
+                                    kind = WarningKind::Skipped, source = &entry.path();
+                                    "Skipping '{}', could not read its file type. Error: {}",
+                                    entry.path().display(),
+                                    error
+                                );
+                                continue;
+                            }
+                        };
+
+                        // Symlinks are resolved only when asked to, `file_type` is then the
+                        // type of their target.
+                        if file_type.is_symlink() && walk.follow_symlinks {
+                            match fs::metadata(entry.path()) {
+                                Ok(metadata) => file_type = metadata.file_type(),
+                                Err(error) => {
+                                    warn!(
+                                        kind = WarningKind::Skipped, source = &entry.path();
+                                        "Skipping '{}', broken symlink. Error: {}",
+                                        entry.path().display(),
+                                        error
+                                    );
+                                    continue;
+                                }
+                            }
+                        }
+
+                        if entry.path() == walk.output
+                            || entry.path() == walk.static_dir
+                            || walk
+                                .exclusions
+                                .is_excluded(&entry.path(), file_type.is_dir())
+                        {
+                            continue;
+                        }
+
+                        if file_type.is_dir() {
+                            let mut recursively_obtained = list_markdown_files(&entry.path(), walk);
+                            files.append(&mut recursively_obtained);
+                        } else {
+                            let path = entry.path();
+
+                            if is_markdown_file(walk.extensions, &path) {
+                                if !is_too_large(walk.max_file_size, &path) {
+                                    files.push(path);
+                                }
+                            } else if file_type.is_file() {
+                                walk.others.push(path);
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        warn!(kind = WarningKind::Skipped; "Invalid entry found. Error: {}", error);
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            warn!(
+                kind = WarningKind::Skipped, source = path;
+                "Error while opening directory '{}': {}",
+                path.display(),
+                err
+            );
+        }
+    }
+
+    files
+}
+
+// Page title is the first heading of the --title-level we find.
+fn title_heading<'a>(ast_root: &'a AstNode<'a>, level: u8) -> Option<&'a AstNode<'a>> {
+    ast_root
+        .children()
+        .find(|item| match item.data.borrow().value {
+            NodeValue::Heading(ref n) => n.level == level,
+            _ => false,
+        })
+}
+
+fn extract_title<'a>(ast_root: &'a AstNode<'a>, file: &Path, level: u8) -> String {
+    let mut page_title = String::new();
+
+    match title_heading(ast_root, level) {
+        // The heading may be formatted (`# **My** `code``), so the text of all its inlines is used.
+        Some(node) => {
+            page_title = node.collect_text().trim().to_owned();
+
+            if page_title.is_empty() {
+                info!(
+                    "Could not find title (empty?) for file '{}', using its file name instead.",
+                    file.display()
+                );
+            }
+        }
+        None => {
+            info!("Could not find title for file '{}'. Consider adding a header level {}: `{} My title` at the beginning of your page. Using its file name instead.", file.display(), level, "#".repeat(level.into()));
+        }
+    }
+
+    if page_title.trim().is_empty() {
+        return humanize_file_name(file);
+    }
+
+    page_title
+}
+
+// Turns `my_first-page.md` into `My First Page`.
+fn humanize_file_name(file: &Path) -> String {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+
+    stem.split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut characters = word.chars();
+            match characters.next() {
+                Some(first) => first.to_uppercase().chain(characters).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+const MORE_MARKER: &str = "<!-- more -->";
+
+fn is_more_marker<'a>(node: &'a AstNode<'a>) -> bool {
+    match node.data.borrow().value {
+        NodeValue::HtmlBlock(ref html) => html.literal.trim() == MORE_MARKER,
+        _ => false,
+    }
+}
+
+// True for html made of comments only, `<!--[if IE]>` conditional comments aside.
+fn is_comment(html: &str) -> bool {
+    let mut rest = html.trim_start();
+
+    while !rest.is_empty() {
+        if !rest.starts_with("<!--") || rest.starts_with("<!--[if") {
+            return false;
+        }
+
+        rest = match rest[4..].find("-->") {
+            Some(end) => rest[4 + end + 3..].trim_start(),
+            None => return false,
+        };
+    }
+
+    true
+}
+
+// Removes the html comments of the document for --strip-comments, the `<!-- more -->` markers
+// aside. Html other than comments is kept, even next to a comment.
+fn strip_comments<'a>(ast_root: &'a AstNode<'a>) {
+    let comments: Vec<&AstNode> = ast_root
+        .descendants()
+        .filter(|node| match node.data.borrow().value {
+            NodeValue::HtmlBlock(ref html) => is_comment(&html.literal),
+            NodeValue::HtmlInline(ref html) => is_comment(html),
+            _ => false,
+        })
+        .filter(|node| !is_more_marker(node))
+        .collect();
+
+    for comment in comments {
+        comment.detach();
+    }
+}
+
+// The summary of a page is everything before a `<!-- more -->` line but the headings, or its
+// first paragraph.
+fn extract_excerpt<'a>(ast_root: &'a AstNode<'a>) -> String {
+    if ast_root.children().any(is_more_marker) {
+        return ast_root
+            .children()
+            .take_while(|node| !is_more_marker(node))
+            .filter(|node| !matches!(node.data.borrow().value, NodeValue::Heading(_)))
+            .map(extract_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+
+    ast_root
+        .children()
+        .find(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .map(extract_text)
+        .unwrap_or_default()
+}
+
+// Cuts the text after at most `limit` characters, at the end of a word when there is one, and
+// adds an ellipsis.
+fn truncate(text: &str, limit: usize) -> String {
+    let end = match text.char_indices().nth(limit) {
+        Some((end, _)) => end,
+        None => return text.to_owned(),
+    };
+
+    let cut = &text[..end];
+    let cut = match text[end..].starts_with(' ') {
+        true => cut,
+        false => cut.rfind(' ').map_or(cut, |space| &cut[..space]),
+    };
+
+    format!("{}…", cut.trim_end())
+}
+
+// Like `collect_text`, with blocks separated by a space so that paragraphs don't run into
+// each other. Whitespace is collapsed.
+fn extract_text<'a>(ast_root: &'a AstNode<'a>) -> String {
+    let mut text = String::new();
+
+    for node in ast_root.descendants() {
+        let value = &node.data.borrow().value;
+
+        if value.block() {
+            text.push(' ');
+        }
+
+        match value {
+            NodeValue::Text(literal) => text.push_str(literal),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::CodeBlock(code_block) => text.push_str(&code_block.literal),
+            NodeValue::LineBreak | NodeValue::SoftBreak => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+const WORDS_PER_MINUTE: usize = 200;
+
+// Only the text and inline code nodes are counted, so that neither the markdown syntax nor the
+// content of code blocks count as words.
+fn count_words<'a>(ast_root: &'a AstNode<'a>) -> usize {
+    ast_root
+        .descendants()
+        .map(|node| match node.data.borrow().value {
+            NodeValue::Text(ref text) => text.split_whitespace().count(),
+            NodeValue::Code(ref code) => code.literal.split_whitespace().count(),
+            _ => 0,
+        })
+        .sum()
+}
+
+// When `render_html` is false only the page's information (title, metadata...) is extracted and
+// `html_content` is left empty. The page is checked either way (missing images, --lint-headings),
+// so that its warnings are reported when it is up to date as well. With --format commonmark, `html_content` is the normalized markdown
+// of the page, its includes and links left as they are written.
+fn md_to_file_data(
+    file: &Path,
+    file_content: &str,
+    renderer: &Renderer,
+    render_html: bool,
+) -> Result<FileData, GenericError> {
+    let arena = Arena::new();
+
+    let (metadata, body) = frontmatter::extract(file_content).map_err(|error| {
+        GenericError::new(format!(
+            "Invalid frontmatter in file '{}'. Error: {}",
+            file.display(),
+            error
+        ))
+    })?;
+
+    let commonmark = renderer.format == Format::Commonmark;
+    let markdown = match commonmark {
+        true => Cow::Borrowed(body),
+        false => {
+            let root = renderer
+                .input_directories
+                .iter()
+                .find(|input| file.starts_with(input))
+                .unwrap_or(&renderer.input_directories[0]);
+            include::expand_includes(body, root, renderer.encoding).map_err(GenericError::new)?
+        }
+    };
+
+    let ast_root = comrak::parse_document(&arena, &markdown, &renderer.options);
+    if renderer.strip_comments {
+        strip_comments(ast_root);
+    }
+
+    // A title from the frontmatter takes precedence over the page's heading.
+    let page_title = match metadata.get("title") {
+        Some(title) => title.to_owned(),
+        None => extract_title(ast_root, file, renderer.title_level),
+    };
+
+    // Checked against the urls as written, before they are rewritten.
+    if !commonmark {
+        let images = images::Images {
+            directory: file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            roots: renderer.images_roots.clone(),
+            ..Default::default()
+        };
+
+        for url in images::find_missing(ast_root, &images) {
+            warn!(
+                kind = WarningKind::MissingImage, source = file;
+                "Image '{}' of '{}' doesn't exist in the input.",
+                url,
+                file.display()
+            );
+        }
+    }
+
+    let moved = renderer.pretty_urls && !is_index_page(file) && !is_not_found_page(file);
+    if !commonmark {
+        links::rewrite_markdown_links(
+            ast_root,
+            &links::Rewriting {
+                extensions: &renderer.extensions,
+                output_extension: &renderer.output_extension,
+                pretty_urls: renderer.pretty_urls,
+                slugify: renderer.slugify,
+                slug_style: renderer.slug_style,
+                moved,
+                base_path: &renderer.base_path,
+            },
+        );
+    }
+
+    let headings =
+        toc::collect_headings(ast_root, &renderer.heading_id_prefix, renderer.slug_style);
+    if renderer.lint_headings {
+        for (heading, previous) in toc::skipped_levels(&headings) {
+            warn!(
+                kind = WarningKind::Heading, source = file;
+                "Heading '{}' of '{}' is level {} but follows a level {} heading.",
+                heading.text,
+                file.display(),
+                heading.level,
+                previous
+            );
+        }
+    }
+    // The heading of the page title isn't listed in its table of contents, the others of its
+    // level are.
+    let title_index = title_heading(ast_root, renderer.title_level)
+        .and_then(|heading| toc::heading_index(ast_root, heading));
+    let word_count = count_words(ast_root);
+    let excerpt = match metadata.get("description") {
+        Some(description) => description.to_owned(),
+        None => truncate(&extract_excerpt(ast_root), renderer.excerpt_length),
+    };
+    let mut file_data = FileData {
+        html_content: String::new(),
+        title: page_title,
+        toc: toc::render(&headings, title_index),
+        excerpt,
+        metadata,
+        word_count,
+        reading_time: word_count.div_ceil(WORDS_PER_MINUTE),
+        text: extract_text(ast_root),
+        links: links::collect_relative_links(ast_root),
+        // Set by `read_page`.
+        includes: Vec::new(),
+    };
+
+    if !render_html {
+        return Ok(file_data);
+    }
+
+    if commonmark {
+        // The frontmatter is kept as it is written.
+        let frontmatter = &file_content[..file_content.len() - body.len()];
+        file_data.html_content.push_str(frontmatter);
+
+        return comrak::format_commonmark(ast_root, &renderer.options, &mut file_data.html_content)
+            .map(|_| file_data)
+            .map_err(|_| GenericError::new("Could not format markdown.".to_owned()));
+    }
+
+    let heading_ids = toc::HeadingIds::new(&headings, renderer.heading_anchors);
+
+    let mut plugins = Plugins::default();
+    plugins.render.heading_adapter = Some(&heading_ids);
+    plugins.render.codefence_syntax_highlighter = renderer
+        .highlighter
+        .as_ref()
+        .map(|highlighter| highlighter as &dyn SyntaxHighlighterAdapter);
+
+    let formatted = if renderer.lazy_images || renderer.code_copy {
+        let images = renderer.lazy_images.then(|| images::Images {
+            directory: file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            roots: renderer.images_roots.clone(),
+            base_path: renderer.base_path.clone(),
+            moved,
+        });
+
+        formatter::Formatter::format_document_with_plugins(
+            ast_root,
+            &renderer.options,
+            &mut file_data.html_content,
+            &plugins,
+            formatter::Changes {
+                images,
+                code_copy: renderer.code_copy,
+            },
+        )
+        .map(|_| ())
+    } else {
+        comrak::format_html_with_plugins(
+            ast_root,
+            &renderer.options,
+            &mut file_data.html_content,
+            &plugins,
+        )
+    };
+
+    if formatted.is_err() {
+        return Err(GenericError::new("Could not format html.".to_owned()));
+    }
+
+    // The raw html of the markdown is already left out by comrak, this covers the html written
+    // for the extensions and the highlighting as well.
+    if renderer.sanitize {
+        file_data.html_content = sanitize::sanitize(&file_data.html_content);
+    }
+
+    Ok(file_data)
+}
+
+// Create the folders path (equivalent to mkdir -p <path>)
+// file is expected to have a filename to it. Files are written from several threads, another
+// thread creating the directory first isn't an error as long as it is a directory.
+fn create_output_file_path(
+    parameters: &Opt,
+    file: &Path,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    if parameters.dry_run {
+        return Ok(());
+    }
+
+    let mut path = file.to_path_buf();
+    path.pop();
+    match fs::create_dir_all(&path) {
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists && path.is_dir() => Ok(()),
+        created => created.map_err(|error| error.into()),
+    }
+}
+
+// The input directory containing the file, which is expected to be absolute.
+fn input_root<'a>(parameters: &'a Opt, file: &Path) -> Option<&'a Path> {
+    parameters
+        .inputs
+        .iter()
+        .find(|input| file.starts_with(input))
+        .map(PathBuf::as_path)
+}
+
+// Expects all input paths to be absolutes (input directory, output directory, file)
+// Every input directory is written to the root of the output.
+fn destination_for_file(
+    parameters: &Opt,
+    file: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error + 'static>> {
+    // Both are resolved by `normalize`, a relative file isn't inside any input directory.
+    if !parameters.output.is_absolute() {
+        return Err(Box::new(GenericError::new(format!(
+            "The output directory '{}' isn't an absolute path.",
+            parameters.output.display()
+        ))));
+    }
+
+    let root = input_root(parameters, file).ok_or_else(|| {
+        GenericError::new(format!(
+            "'{}' isn't inside an input directory.",
+            file.display()
+        ))
+    })?;
+
+    Ok(parameters.output.join(file.strip_prefix(root)?))
+}
+
+fn read_file_string(file: &PathBuf) -> Result<String, String> {
+    let path = Path::new(&file);
+
+    if path.exists() {
+        match fs::read_to_string(path) {
+            Ok(content) => return Ok(content),
+            Err(error) => {
+                let error = format!("Could not read file '{}'. Error: {}", file.display(), error);
+                error!("{}", error);
+                return Err(error);
+            }
+        }
+    }
+
+    let error = format!("Couldn't find file '{}'", file.display());
+    warn!(kind = WarningKind::MissingFile, source = path; "{}", error);
+    Err(error)
+}
+
+const CONTENT_PLACEHOLDER: &str = "content";
+
+// Every placeholder filled by `write_page` and `assemble_file`, templates may only use these.
+const PLACEHOLDERS: &[&str] = &[
+    "title",
+    "excerpt",
+    "toc",
+    CONTENT_PLACEHOLDER,
+    "year",
+    "date",
+    "nav",
+    "breadcrumbs",
+    "prev_link",
+    "next_link",
+    "word_count",
+    "reading_time",
+    "description",
+    "og_image",
+    "author",
+    "source_path",
+    "last_modified",
+    "head_includes",
+    "json_ld",
+    "canonical",
+];
+
+fn write_pieces(destination: &Path, pieces: &[&str]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(destination)?);
+
+    for piece in pieces {
+        writer.write_all(piece.as_bytes())?;
+    }
+
+    writer.flush()
+}
+
+// When the header contains `{content}` it is used as the layout of the whole page and the footer
+// is ignored. Otherwise the page is the header, followed by the content then the footer.
+// `placeholders` are the (name, value) pairs filled in both the header and the footer.
+fn assemble_file(
+    parameters: &Opt,
+    file_data: &FileData,
+    header: &Template,
+    footer: &Template,
+    placeholders: &[(&str, &str)],
+    fingerprints: &Fingerprints,
+    destination: &Path,
+) -> Result<(), GenericError> {
+    let excerpt = sitemap::escape_xml(&file_data.excerpt);
+    // A title from the frontmatter is written as it is otherwise.
+    let title = match parameters.sanitize {
+        true => Cow::Owned(sitemap::escape_xml(&file_data.title)),
+        false => Cow::Borrowed(file_data.title.as_str()),
+    };
+    let mut values = placeholders.to_vec();
+    values.extend_from_slice(&[
+        ("title", &title),
+        ("excerpt", &excerpt),
+        ("toc", &file_data.toc),
+    ]);
+    let is_set = |key: &str| file_data.metadata.contains_key(key);
+
+    // The page is written piece by piece, without being copied.
+    let pieces = match parameters.format {
+        Format::Html if header.contains(CONTENT_PLACEHOLDER) => {
+            values.push((CONTENT_PLACEHOLDER, &file_data.html_content));
+            header.pieces(&values, &is_set)
+        }
+        Format::Html => {
+            let mut pieces = header.pieces(&values, &is_set);
+            pieces.push(&file_data.html_content);
+            pieces.extend(footer.pieces(&values, &is_set));
+            pieces
+        }
+        Format::Fragment | Format::Commonmark => vec![file_data.html_content.as_str()],
+    };
+
+    let rewritten;
+    let pieces = if fingerprints.is_empty() || parameters.format == Format::Commonmark {
+        pieces
+    } else {
+        let page = destination
+            .strip_prefix(&parameters.output)
+            .unwrap_or(destination);
+        let base_path = parameters
+            .base_url
+            .as_deref()
+            .map(links::base_path)
+            .unwrap_or_default();
+
+        rewritten = fingerprints.rewrite_html(&pieces.concat(), page, base_path);
+        vec![rewritten.as_str()]
+    };
+
+    if parameters.dry_run {
+        info!("Dry run: would write '{}'.", destination.display());
+        return Ok(());
+    }
+
+    let written = if parameters.minify && parameters.format != Format::Commonmark {
+        fs::write(destination, minify::minify(&pieces.concat()))
+    } else {
+        write_pieces(destination, &pieces)
+    };
+
+    written.map_err(|error| {
+        GenericError::new(format!(
+            "Could not write to file '{}'. Error: {}",
+            destination.display(),
+            error
+        ))
+    })?;
+
+    if !parameters.compress {
+        return Ok(());
+    }
+
+    compress::write_gzip(destination, parameters.compression_level).map_err(|error| {
+        GenericError::new(format!(
+            "Could not compress file '{}'. Error: {}",
+            destination.display(),
+            error
+        ))
+    })
+}
+
+// None when the file doesn't exist, or its modification time can't be read.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// A page is up to date when its output is newer than both its source and the templates.
+// The partials included are sources of the page as well.
+fn is_up_to_date(
+    source: &Path,
+    includes: &[PathBuf],
+    destination: &Path,
+    templates: &Templates,
+) -> bool {
+    let output_modified = match modified_time(destination) {
+        Some(modified) => modified,
+        None => return false,
+    };
+
+    let is_older =
+        |path: &Path| modified_time(path).is_some_and(|modified| modified < output_modified);
+    let templates_are_older = templates
+        .modified
+        .is_none_or(|modified| modified < output_modified);
+
+    is_older(source) && includes.iter().all(|include| is_older(include)) && templates_are_older
+}
+
+// The partials of `{{> path/to/partial.md}}` lines, relative to the file's input directory.
+// Markdown is written as it is, without including anything.
+fn included_files(parameters: &Opt, file: &Path, content: &str) -> Vec<PathBuf> {
+    if parameters.format == Format::Commonmark {
+        return Vec::new();
+    }
+
+    let root = input_root(parameters, file).unwrap_or(&parameters.input);
+    include::included_files(content, root, parameters.encoding)
+}
+
+struct ProcessedFile {
+    destination: PathBuf,
+    skipped: bool,
+    data: FileData,
+}
+
+fn is_index_page(file: &Path) -> bool {
+    file.file_stem().is_some_and(|stem| stem == "index")
+}
+
+const NOT_FOUND_PAGE: &str = "404.html";
+
+// A `404.md` is written to the root of the output whatever its directory, where the hosts look
+// for it. It is served for any missing path, so its links had better be root-relative.
+fn is_not_found_page(file: &Path) -> bool {
+    file.file_stem().is_some_and(|stem| stem == "404")
+}
+
+// `page.md` is written to `page.html`, or with --pretty-urls to `page/index.html`, `html` being
+// the --output-extension. The `404` page is always written to `404.html`.
+// --slugify turns every name of the path into a slug: `My Notes/Day 1.md` to `my-notes/day-1.html`.
+// `index.md` is always written to `index.html`. With --format commonmark, `page.md` is written to
+// `page.md`.
+fn page_destination(parameters: &Opt, file: &Path) -> Result<PathBuf, GenericError> {
+    let mut destination = destination_for_file(parameters, file)
+        .map_err(|error| GenericError::new(error.to_string()))?;
+
+    if parameters.format == Format::Commonmark {
+        return Ok(destination);
+    }
+
+    if is_not_found_page(file) {
+        return Ok(parameters.output.join(NOT_FOUND_PAGE));
+    }
+
+    if parameters.slugify {
+        destination = slugify_destination(parameters, &destination)?;
+    }
+
+    if parameters.pretty_urls && !is_index_page(file) {
+        destination.set_extension("");
+        destination.push(index_page_name(parameters));
+    } else {
+        destination.set_extension(&parameters.output_extension);
+    }
+
+    Ok(destination)
+}
+
+// The destination with every name under the output directory turned into a slug, for --slugify.
+// The pages and the assets of the input directory are both renamed, so that their relative links
+// still agree.
+fn slugify_destination(parameters: &Opt, destination: &Path) -> Result<PathBuf, GenericError> {
+    let relative = destination
+        .strip_prefix(&parameters.output)
+        .map_err(|error| GenericError::new(error.to_string()))?;
+
+    Ok(parameters
+        .output
+        .join(slug::slugify_path(relative, slug_style(parameters))))
+}
+
+// The `permalink:` of the frontmatter, relative to the output directory whether or not it starts
+// with a `/`. `/about/team/` and `/about/team` are written to `about/team/index.html`, a name with
+// an extension such as `/about/team.html` is written as it is. The pages are written where their
+// source puts them with --format commonmark.
+fn permalink_destination(
+    parameters: &Opt,
+    file: &Path,
+    content: &str,
+) -> Result<Option<PathBuf>, GenericError> {
+    if parameters.format == Format::Commonmark {
+        return Ok(None);
+    }
+
+    // An invalid frontmatter is reported when the page is converted.
+    let Some(permalink) = frontmatter::extract(content)
+        .ok()
+        .and_then(|(metadata, _)| metadata.get("permalink").cloned())
+    else {
+        return Ok(None);
+    };
+
+    let segments: Vec<&str> = permalink
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments
+        .iter()
+        .any(|segment| *segment == "." || *segment == "..")
+    {
+        return Err(GenericError::new(format!(
+            "Invalid permalink '{}' in '{}', it can't contain '.' or '..'.",
+            permalink,
+            file.display()
+        )));
+    }
+
+    let mut destination = parameters.output.clone();
+    destination.extend(&segments);
+
+    let is_file = !permalink.ends_with('/')
+        && segments.last().is_some_and(|name| {
+            name.rsplit_once('.')
+                .is_some_and(|(stem, _)| !stem.is_empty())
+        });
+    if !is_file {
+        destination.push(index_page_name(parameters));
+    }
+
+    Ok(Some(destination))
+}
+
+// Where the pages written at their permalink are, by the destination their source would give them,
+// relative to the output directory.
+struct Permalinks<'a> {
+    moved: &'a HashMap<PathBuf, PathBuf>,
+    index: &'a str,
+}
+
+impl Permalinks<'_> {
+    // Points a relative url of a page to the pages written at their permalink. The urls of a page
+    // written at its permalink are written against `computed`, they are made relative to its
+    // `destination`. None when the url is kept.
+    fn rewrite(&self, url: &str, computed: &Path, destination: &Path) -> Option<String> {
+        if url.is_empty() || url.starts_with(['#', '/']) || links::is_external(url) {
+            return None;
+        }
+
+        let target = links::resolve_link(computed, url, self.index)?;
+        let (target, is_moved) = match self.moved.get(&target) {
+            Some(permalink) => (permalink, true),
+            None if computed == destination => return None,
+            None => (&target, false),
+        };
+
+        let path_end = url.find(['#', '?']).unwrap_or(url.len());
+        let suffix = &url[path_end..];
+        let points_to_directory = url[..path_end].is_empty() || url[..path_end].ends_with('/');
+
+        // A page written at `/about/team/` is linked as a directory.
+        let url = links::relative_url(destination, target);
+        let as_directory = target.ends_with(self.index) && (points_to_directory || is_moved);
+        Some(match url.strip_suffix(self.index) {
+            Some("") if as_directory => format!("./{}", suffix),
+            Some(directory) if as_directory => format!("{}{}", directory, suffix),
+            _ => format!("{}{}", url, suffix),
+        })
+    }
+}
+
+// `index.html`, with the --output-extension.
+fn index_page_name(parameters: &Opt) -> String {
+    match parameters.output_extension.as_str() {
+        "" => "index".to_owned(),
+        extension => format!("index.{}", extension),
+    }
+}
+
+// The absolute url of a page from its path relative to the output directory. With --pretty-urls
+// it is that of its directory, `post/` rather than `post/index.html`, as the links to it.
+fn page_url(parameters: &Opt, base_url: &str, relative_path: &Path) -> String {
+    let directory = relative_path
+        .parent()
+        .filter(|_| parameters.pretty_urls)
+        .filter(|_| relative_path.file_name() == Some(index_page_name(parameters).as_ref()));
+
+    match directory {
+        Some(directory) => {
+            let url = links::absolute_url(base_url, directory);
+            match url.ends_with('/') {
+                true => url,
+                false => url + "/",
+            }
+        }
+        None => links::absolute_url(base_url, relative_path),
+    }
+}
+
+fn slug_style(parameters: &Opt) -> slug::Style {
+    slug::Style {
+        separator: parameters.slug_separator,
+        lowercase: parameters.slug_lowercase,
+    }
+}
+
+// A panic while converting a page, from comrak on a pathological document for instance, only
+// fails that page instead of stopping the build.
+fn convert_file(
+    file: &Path,
+    content: &str,
+    renderer: &Renderer,
+    render_html: bool,
+) -> Result<FileData, GenericError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        md_to_file_data(file, content, renderer, render_html)
+    }))
+    .unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|reason| reason.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown reason".to_owned());
+
+        Err(GenericError::new(format!(
+            "The conversion panicked: {}",
+            reason
+        )))
+    })
+}
+
+// The text of a markdown file. A file which isn't valid in the --encoding is skipped with a
+// warning, rather than failing the build.
+fn read_markdown(parameters: &Opt, file: &Path) -> Result<Option<String>, GenericError> {
+    let bytes = fs::read(file).map_err(|error| {
+        GenericError::new(format!(
+            "Could not read file '{}'. Error: {}",
+            file.display(),
+            error
+        ))
+    })?;
+
+    let content = encoding::decode(bytes, parameters.encoding);
+    if content.is_none() {
+        warn!(
+            kind = WarningKind::Skipped, source = file;
+            "Skipping '{}', it isn't valid UTF-8. The --encoding option reads the files in another encoding.",
+            file.display()
+        );
+    }
+
+    Ok(content)
+}
+
+// Converts a single markdown file, the page is written by `write_page`. None when the file is
+// skipped.
+fn read_page(
+    parameters: &Opt,
+    file: &Path,
+    templates: &Templates,
+    renderer: &Renderer,
+) -> Result<Option<ProcessedFile>, GenericError> {
+    let Some(content) = read_markdown(parameters, file)? else {
+        return Ok(None);
+    };
+    let destination = match permalink_destination(parameters, file, &content) {
+        Ok(Some(destination)) => destination,
+        Ok(None) => page_destination(parameters, file)?,
+        Err(error) => {
+            warn!(kind = WarningKind::Frontmatter, source = file; "{}", error.message);
+            page_destination(parameters, file)?
+        }
+    };
+
+    let includes = included_files(parameters, file, &content);
+    let convert = |render_html| -> Result<FileData, GenericError> {
+        Ok(FileData {
+            includes: includes.clone(),
+            ..convert_file(file, &content, renderer, render_html)?
+        })
+    };
+
+    // Nothing is written with --frontmatter-only.
+    if parameters.frontmatter_only {
+        return Ok(Some(ProcessedFile {
+            destination,
+            skipped: true,
+            data: convert(false)?,
+        }));
+    }
+
+    // Up-to-date pages are still read since the site-wide files (feed, sitemap...) list them.
+    // The navigation lists every page, a page using it is never up to date. Neither is one which
+    // may point to fingerprinted assets, since they may have changed. With --single-file every
+    // page is written again.
+    let rebuilt = parameters.force
+        || parameters.fingerprint
+        || parameters.single_file.is_some()
+        || templates.lists_pages;
+    if !rebuilt && is_up_to_date(file, &includes, &destination, templates) {
+        return Ok(Some(ProcessedFile {
+            destination,
+            skipped: true,
+            data: convert(false)?,
+        }));
+    }
+
+    if parameters.dry_run {
+        info!("Dry run: would convert '{}'.", file.display());
+    } else {
+        debug!("Processing file {}", file.display());
+    }
+
+    Ok(Some(ProcessedFile {
+        destination,
+        skipped: false,
+        data: convert(true)?,
+    }))
+}
+
+// Writes the assembled page to the output directory. `source` is the markdown file of the page.
+fn write_page(
+    parameters: &Opt,
+    source: &Path,
+    page: &ProcessedFile,
+    templates: &Templates,
+    listings: Option<&Listings>,
+    fingerprints: &Fingerprints,
+) -> Result<(), GenericError> {
+    let file_data = &page.data;
+    let destination = &page.destination;
+
+    // A layout is a whole page, it takes the place of the header and the footer.
+    let no_footer = Template::default();
+    let (header, footer) = match file_data.metadata.get("layout") {
+        Some(name) => match templates.layouts.get(name) {
+            Some(layout) => (layout, &no_footer),
+            None => {
+                return Err(GenericError::new(format!(
+                    "Unknown layout '{}', '{}' doesn't exist.",
+                    name,
+                    parameters.layouts.join(format!("{}.html", name)).display()
+                )))
+            }
+        },
+        None => templates.for_page(source),
+    };
+
+    let relative_destination = destination
+        .strip_prefix(&parameters.output)
+        .unwrap_or(destination);
+
+    let year = templates.build_date.format("%Y").to_string();
+    let date = templates.build_date.format("%Y-%m-%d").to_string();
+    let nav = listings
+        .map(|listings| listings.nav.render(relative_destination))
+        .unwrap_or_default();
+    let breadcrumbs = listings
+        .map(|listings| listings.breadcrumbs.render(relative_destination))
+        .unwrap_or_default();
+    let (prev_link, next_link) = listings
+        .map(|listings| listings.pager.render(relative_destination))
+        .unwrap_or_default();
+    let word_count = file_data.word_count.to_string();
+    let reading_time = file_data.reading_time.to_string();
+
+    // Meant for the attributes of `<meta>` tags, a missing value leaves them empty.
+    let description = sitemap::escape_xml(&file_data.excerpt);
+    let metadata_attribute = |key: &str| {
+        file_data
+            .metadata
+            .get(key)
+            .map(|value| sitemap::escape_xml(value))
+            .unwrap_or_default()
+    };
+    let og_image = metadata_attribute("image");
+    let author = metadata_attribute("author");
+
+    // Relative to the input directory, with forward slashes, for the "edit this page" links. The
+    // pages generated without a source, such as the tag pages, have none.
+    let source_path = match input_root(parameters, source) {
+        Some(root) if source.is_file() => source
+            .strip_prefix(root)
+            .unwrap_or(source)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        _ => String::new(),
+    };
+    let source_path = sitemap::escape_xml(&source_path);
+
+    // The absolute url of the page, as in the sitemap, known with a --base-url only.
+    let url = parameters
+        .base_url
+        .as_deref()
+        .map(|base_url| page_url(parameters, base_url, relative_destination));
+    let canonical = url
+        .as_deref()
+        .map(|url| {
+            format!(
+                "<link rel=\"canonical\" href=\"{}\">",
+                sitemap::escape_xml(url)
+            )
+        })
+        .unwrap_or_default();
+
+    // An invalid date is reported by the feed.
+    let json_ld = match parameters.json_ld {
+        true => json_ld::render(
+            &file_data.title,
+            &file_data.excerpt,
+            file_data
+                .metadata
+                .get("date")
+                .and_then(|date| frontmatter::parse_date(date)),
+            file_data.metadata.get("author").map(String::as_str),
+            url.as_deref(),
+        ),
+        false => String::new(),
+    };
+
+    // Empty when the file system doesn't tell, and for the pages generated without a source.
+    let last_modified = modified_time(source)
+        .map(|modified| {
+            DateTime::<Utc>::from(modified)
+                .format(&parameters.last_modified_format)
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    let placeholders = [
+        ("year", year.as_str()),
+        ("date", &date),
+        ("nav", &nav),
+        ("breadcrumbs", breadcrumbs),
+        ("prev_link", prev_link),
+        ("next_link", next_link),
+        ("word_count", &word_count),
+        ("reading_time", &reading_time),
+        ("description", &description),
+        ("og_image", &og_image),
+        ("author", &author),
+        ("source_path", &source_path),
+        ("last_modified", &last_modified),
+        ("head_includes", &templates.head_includes),
+        ("json_ld", &json_ld),
+        ("canonical", &canonical),
+    ];
+
+    create_output_file_path(parameters, destination)
+        .map_err(|error| GenericError::new(error.to_string()))?;
+    assemble_file(
+        parameters,
+        file_data,
+        header,
+        footer,
+        &placeholders,
+        fingerprints,
+        destination,
+    )
+}
+
+// Converts a single markdown file and writes the assembled page to the output directory, on its
+// own. Site-wide placeholders such as `{nav}` or `{breadcrumbs}` are left empty, `build` fills
+// them.
+fn process_file(
+    parameters: &Opt,
+    file: &Path,
+    templates: &Templates,
+    renderer: &Renderer,
+) -> Result<Option<ProcessedFile>, GenericError> {
+    let Some(page) = read_page(parameters, file, templates, renderer)? else {
+        return Ok(None);
+    };
+
+    if !page.skipped {
+        write_page(
+            parameters,
+            file,
+            &page,
+            templates,
+            None,
+            &Fingerprints::default(),
+        )?;
+    }
+
+    Ok(Some(page))
+}
+
+// 1. Merges the settings from the input directory's configuration file, if any.
+// 2. Validates the input directory exists and is not a file.
+// 3. Creates the base output directory.
+// 4. Converts the input and output directory to absolute paths.
+// 5. Validates the output directory isn't the input directory.
+// 6. Lowercases the markdown extensions.
+// Merges the settings of `webmark.toml` into those of the command line, then `normalize`s them.
+fn normalize_program_arguments(
+    parameters: &Opt,
+    matches: &ArgMatches,
+) -> Result<Opt, GenericError> {
+    let mut parameters = parameters.clone();
+    let given = |name: &str| matches.occurrences_of(name) > 0;
+
+    let config_directory = parameters.inputs[0].clone();
+    if let Some(config) = Config::load(&config_directory.join(config::CONFIG_FILE))? {
+        config.merge_into(&mut parameters, &given, &config_directory);
+    }
+
+    parameters.header_given = given("header-file");
+    parameters.footer_given = given("footer-file");
+    parameters.assets_given = given("assets-file");
+
+    normalize(&parameters)
+}
+
+// Checks the settings and resolves their paths: the input and output directories become absolute,
+// the site's files are found from the first input directory and the templates from the template
+// directory. The output directory is created. Settings which are already normalized are kept as
+// they are.
+fn normalize(parameters: &Opt) -> Result<Opt, GenericError> {
+    if parameters.normalized {
+        return Ok(parameters.clone());
+    }
+
+    if parameters.inputs.is_empty() {
+        return Err(GenericError::new(
+            "At least one input directory is needed.".to_owned(),
+        ));
+    }
+
+    for input in &parameters.inputs {
+        if !input.exists() {
+            return Err(GenericError::new(format!(
+                "Input directory '{}' was not found.",
+                input.display()
+            )));
+        }
+    }
+
+    if !parameters.output.exists() && !parameters.dry_run {
+        if let Err(error) = fs::create_dir_all(&parameters.output) {
+            return Err(GenericError::new(format!(
+                "Could not create output directory. Error: {}",
+                error
+            )));
+        }
+    }
+
+    let mut new_parameters = parameters.clone();
+
+    new_parameters.inputs.clear();
+
+    for input in &parameters.inputs {
+        match input.canonicalize() {
+            Ok(path) => {
+                new_parameters.inputs.push(path);
+            }
+            Err(error) => {
+                return Err(GenericError::new(format!(
+                    "Could not resolve path for input directory '{}'. Error: {}",
+                    input.display(),
+                    error
+                )));
+            }
+        }
+    }
+
+    new_parameters.input = new_parameters.inputs[0].clone();
+
+    // A file inside both would be read twice.
+    for (index, input) in new_parameters.inputs.iter().enumerate() {
+        for other in &new_parameters.inputs[index + 1..] {
+            if input.starts_with(other) || other.starts_with(input) {
+                return Err(GenericError::new(format!(
+                    "Input directories '{}' and '{}' overlap.",
+                    input.display(),
+                    other.display()
+                )));
+            }
+        }
+    }
+
+    // A dry run doesn't create the output directory, which can't be canonicalized then.
+    let output = if parameters.dry_run && !parameters.output.exists() {
+        path::absolute(&parameters.output)
+    } else {
+        parameters.output.canonicalize()
+    };
+
+    match output {
+        Ok(path) => {
+            new_parameters.output = path;
+        }
+        Err(error) => {
+            return Err(GenericError::new(format!(
+                "Could not resolve path for output directory '{}'. Error: {}",
+                parameters.output.display(),
+                error
+            )));
+        }
+    }
+
+    if parameters.single_file.is_some() && parameters.format == Format::Commonmark {
+        return Err(GenericError::new(
+            "--single-file writes html, it can't be used with --format commonmark.".to_owned(),
+        ));
+    }
+
+    // chrono panics when writing a date with an invalid format.
+    if StrftimeItems::new(&parameters.last_modified_format).any(|item| item == Item::Error) {
+        return Err(GenericError::new(format!(
+            "Invalid --last-modified-format '{}'.",
+            parameters.last_modified_format
+        )));
+    }
+
+    if parameters.compression_level > 9 {
+        return Err(GenericError::new(format!(
+            "Invalid compression level {}, levels go from 0 to 9.",
+            parameters.compression_level
+        )));
+    }
+
+    // A configuration file isn't checked against the possible values.
+    if !['-', '_'].contains(&parameters.slug_separator) {
+        return Err(GenericError::new(format!(
+            "Invalid slug separator '{}', expected '-' or '_'.",
+            parameters.slug_separator
+        )));
+    }
+
+    if !(1..=6).contains(&parameters.title_level) {
+        return Err(GenericError::new(format!(
+            "Invalid title level {}, headings go from level 1 to 6.",
+            parameters.title_level
+        )));
+    }
+
+    // Relative to the first input directory.
+    new_parameters.static_dir = new_parameters.input.join(&parameters.static_dir);
+    new_parameters.assets_file = new_parameters.input.join(&parameters.assets_file);
+
+    // Relative to the template directory, so that the templates are found wherever the tool is
+    // run from.
+    let template_dir = match &parameters.template_dir {
+        Some(directory) => path::absolute(directory).unwrap_or_else(|_| directory.clone()),
+        None => new_parameters.input.clone(),
+    };
+    new_parameters.header_file = template_dir.join(&parameters.header_file);
+    new_parameters.footer_file = template_dir.join(&parameters.footer_file);
+    new_parameters.layouts = template_dir.join(LAYOUTS_DIRECTORY);
+    new_parameters.head_file = template_dir.join(HEAD_FILE);
+    // Left unset when it isn't given, the templates then being those of the input directory.
+    new_parameters.template_dir = parameters.template_dir.as_ref().map(|_| template_dir);
+
+    // A path other than the default one was given, from the command line, the configuration
+    // file or the library.
+    let given = |given: bool, path: &Path, default: &str| given || path != Path::new(default);
+    new_parameters.header_given = given(
+        parameters.header_given,
+        &parameters.header_file,
+        HEADER_FILE,
+    );
+    new_parameters.footer_given = given(
+        parameters.footer_given,
+        &parameters.footer_file,
+        FOOTER_FILE,
+    );
+    new_parameters.assets_given = given(
+        parameters.assets_given,
+        &parameters.assets_file,
+        ASSETS_FILE,
+    );
+
+    new_parameters.extensions = parameters
+        .extensions
+        .iter()
+        .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+        .filter(|extension| !extension.is_empty())
+        .collect();
+    new_parameters.output_extension = parameters
+        .output_extension
+        .trim()
+        .trim_start_matches('.')
+        .to_owned();
+
+    // The default `./out` is inside the default input `.`, the walk then skips it.
+    for input in &new_parameters.inputs {
+        if new_parameters.output == *input {
+            return Err(GenericError::new(
+                "The output directory can't be an input directory.".to_owned(),
+            ));
+        } else if new_parameters.output.starts_with(input) {
+            debug!(
+                "Output directory '{}' is inside the input directory, it won't be read as input.",
+                new_parameters.output.display()
+            );
+        }
+    }
+
+    new_parameters.normalized = true;
+    Ok(new_parameters)
+}
+
+// Removes everything inside the output directory, keeping the directory itself.
+// Expects normalized (absolute) paths.
+fn clean_output_directory(parameters: &Opt) -> Result<(), GenericError> {
+    // Cleaning an input directory, or one of its parents, would delete the site's sources.
+    if parameters
+        .inputs
+        .iter()
+        .any(|input| input.starts_with(&parameters.output))
+    {
+        return Err(GenericError::new(format!(
+            "Refusing to clean output directory '{}' since it contains an input directory.",
+            parameters.output.display()
+        )));
+    }
+
+    if !parameters.output.exists() {
+        return Ok(());
+    }
+
+    info!(
+        "Cleaning output directory '{}'.",
+        parameters.output.display()
+    );
+
+    let entries = fs::read_dir(&parameters.output).map_err(|error| {
+        GenericError::new(format!(
+            "Could not read output directory '{}'. Error: {}",
+            parameters.output.display(),
+            error
+        ))
+    })?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|error| GenericError::new(error.to_string()))?
+            .path();
+
+        if parameters.dry_run {
+            info!("Dry run: would remove '{}'.", path.display());
+            continue;
+        }
+
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+
+        result.map_err(|error| {
+            GenericError::new(format!(
+                "Could not remove '{}'. Error: {}",
+                path.display(),
+                error
+            ))
+        })?;
+    }
+
+    Ok(())
+}
+
+struct Templates {
+    header: Template,
+    footer: Template,
+    // Most recent modification time of the template files, if any exists.
+    modified: Option<SystemTime>,
+    // Shared by every page of a build for `{year}` and `{date}`.
+    build_date: DateTime<Utc>,
+    // Layouts selected with `layout: name` in a page's frontmatter, by name.
+    layouts: HashMap<String, Template>,
+    // The `header.html` and `footer.html` of the input's directories, by path.
+    overrides: HashMap<PathBuf, Template>,
+    // Whether any template lists the other pages, with `{nav}`, `{breadcrumbs}`, `{prev_link}`
+    // or `{next_link}`.
+    lists_pages: bool,
+    // The `head.html` snippet shared by the templates, empty without one.
+    head_includes: String,
+    // Whether the header and the footer take precedence over the `overrides`.
+    header_chosen: bool,
+    footer_chosen: bool,
+}
+
+// The placeholders listing the other pages of the site.
+const LISTING_PLACEHOLDERS: [&str; 4] = ["nav", "breadcrumbs", "prev_link", "next_link"];
+
+// What the templates show of the other pages, built once every page is read.
+struct Listings {
+    nav: nav::Folder,
+    breadcrumbs: breadcrumbs::Breadcrumbs,
+    pager: pager::Pager,
+}
+
+const HEADER_FILE: &str = "header.html";
+const FOOTER_FILE: &str = "footer.html";
+const ASSETS_FILE: &str = "assets.config";
+const LAYOUTS_DIRECTORY: &str = "layouts";
+const HEAD_FILE: &str = "head.html";
+
+// Every `layouts/<name>.html` file, named after its file name without the extension.
+fn read_layouts(
+    directory: &Path,
+    syntax: &template::Syntax,
+) -> (HashMap<String, Template>, Option<SystemTime>) {
+    let mut layouts = HashMap::new();
+    let mut modified = None;
+
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return (layouts, modified),
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+
+        let name = match (path.file_stem(), path.extension()) {
+            (Some(name), Some(extension)) if extension == "html" => {
+                name.to_string_lossy().into_owned()
+            }
+            _ => continue,
+        };
+
+        if let Ok(layout) = read_file_string(&path) {
+            modified = modified.max(modified_time(&path));
+            layouts.insert(name, Template::parse(&layout, syntax, PLACEHOLDERS));
+        }
+    }
+
+    (layouts, modified)
+}
+
+// A template which wasn't chosen explicitly is optional: there is no warning when it's missing.
+fn read_template(path: &PathBuf, chosen: bool) -> Option<String> {
+    match chosen || path.exists() {
+        true => read_file_string(path).ok(),
+        false => None,
+    }
+}
+
+fn read_templates(arguments: &Opt) -> Templates {
+    let header_chosen = arguments.header_given || arguments.template_dir.is_some();
+    let footer_chosen = arguments.footer_given || arguments.template_dir.is_some();
+
+    // The default header is written in the configured syntax, or its title wouldn't be replaced.
+    let header = read_template(&arguments.header_file, header_chosen).unwrap_or_else(|| {
+        format!(
+            "<html><head><title>{}</title><body>",
+            arguments.placeholder_syntax.placeholder("title")
+        )
+    });
+
+    let footer =
+        read_template(&arguments.footer_file, footer_chosen).unwrap_or("</body></html>".to_owned());
+
+    let (layouts, layouts_modified) =
+        read_layouts(&arguments.layouts, &arguments.placeholder_syntax);
+
+    let head_includes = read_template(&arguments.head_file, false).unwrap_or_default();
+
+    let modified = [
+        arguments.header_file.as_path(),
+        arguments.footer_file.as_path(),
+        arguments.head_file.as_path(),
+    ]
+    .iter()
+    .filter_map(|path| modified_time(path))
+    .max()
+    .max(layouts_modified);
+
+    let syntax = &arguments.placeholder_syntax;
+    let header = Template::parse(&header, syntax, PLACEHOLDERS);
+    let footer = Template::parse(&footer, syntax, PLACEHOLDERS);
+
+    let mut overrides = HashMap::new();
+    let mut overrides_modified = None;
+    for input in &arguments.inputs {
+        find_template_overrides(arguments, input, &mut overrides, &mut overrides_modified);
+    }
+    let modified = modified.max(overrides_modified);
+
+    let lists_pages = LISTING_PLACEHOLDERS.iter().any(|name| {
+        header.contains(name)
+            || footer.contains(name)
+            || layouts.values().any(|layout| layout.contains(name))
+            || overrides.values().any(|template| template.contains(name))
+    });
+
+    Templates {
+        header,
+        footer,
+        modified,
+        build_date: Utc::now(),
+        layouts,
+        overrides,
+        lists_pages,
+        head_includes,
+        header_chosen,
+        footer_chosen,
+    }
+}
+
+// Reads every `header.html` and `footer.html` of the sub-directories of the input directory, by
+// path. Hidden directories and the output directory are skipped. Those of the input directory
+// itself aren't overrides: they are the --header-file and --footer-file by default, and the
+// templates given on the command line or with --template-dir take precedence over them.
+fn find_template_overrides(
+    arguments: &Opt,
+    directory: &Path,
+    overrides: &mut HashMap<PathBuf, Template>,
+    modified: &mut Option<SystemTime>,
+) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() && !hidden && path != arguments.output => {
+                find_template_overrides(arguments, &path, overrides, modified)
+            }
+            Ok(file_type) if file_type.is_file() => {
+                let is_input = arguments.inputs.iter().any(|input| input == directory);
+                if is_input
+                    || (entry.file_name() != HEADER_FILE && entry.file_name() != FOOTER_FILE)
+                {
+                    continue;
+                }
+
+                if let Ok(template) = read_file_string(&path) {
+                    *modified = (*modified).max(modified_time(&path));
+                    let template =
+                        Template::parse(&template, &arguments.placeholder_syntax, PLACEHOLDERS);
+                    overrides.insert(path, template);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Templates {
+    // The nearest `header.html` and `footer.html` from the directory of the page up to the
+    // sub-directories of the input, each of them being otherwise the --header-file and
+    // --footer-file. Those given on the command line, in the configuration file or with
+    // --template-dir are used for every page.
+    fn for_page(&self, source: &Path) -> (&Template, &Template) {
+        let nearest = |name: &str, chosen: bool, default| match chosen {
+            true => default,
+            false => source
+                .ancestors()
+                .skip(1)
+                .find_map(|directory| self.overrides.get(&directory.join(name)))
+                .unwrap_or(default),
+        };
+
+        (
+            nearest(HEADER_FILE, self.header_chosen, &self.header),
+            nearest(FOOTER_FILE, self.footer_chosen, &self.footer),
+        )
+    }
+}
+
+// Settings used to convert every page, created once per build.
+struct Renderer {
+    options: Options<'static>,
+    highlighter: Option<Highlighter>,
+    heading_anchors: bool,
+    heading_id_prefix: String,
+    pretty_urls: bool,
+    // Partials are included relative to the input directory of the page, and read with the
+    // --encoding.
+    input_directories: Vec<PathBuf>,
+    encoding: encoding::Encoding,
+    extensions: Vec<String>,
+    output_extension: String,
+    slugify: bool,
+    slug_style: slug::Style,
+    title_level: u8,
+    excerpt_length: usize,
+    format: Format,
+    lazy_images: bool,
+    code_copy: bool,
+    lint_headings: bool,
+    strip_comments: bool,
+    sanitize: bool,
+    // Root-relative image urls point into these, the input directories and the --static-dir.
+    images_roots: Vec<PathBuf>,
+    // Path of the --base-url, see `links::base_path`.
+    base_path: String,
+}
+
+fn create_highlighter(arguments: &Opt) -> Result<Option<Highlighter>, GenericError> {
+    if arguments.no_highlight {
+        return Ok(None);
+    }
+
+    Highlighter::new(&arguments.highlight_theme)
+        .map(Some)
+        .map_err(GenericError::new)
+}
+
+// Without any flag this is plain CommonMark.
+fn create_options(arguments: &Opt) -> Options<'static> {
+    let mut options = Options::default();
+
+    options.extension.table = arguments.gfm || arguments.tables;
+    options.extension.strikethrough = arguments.gfm || arguments.strikethrough;
+    options.extension.autolink = arguments.gfm || arguments.autolink;
+    options.extension.tasklist = arguments.gfm || arguments.tasklist;
+    options.render.tasklist_classes = options.extension.tasklist;
+    options.extension.footnotes = arguments.footnotes;
+    options.extension.shortcodes = arguments.emoji;
+    options.extension.description_lists = arguments.description_lists;
+    options.extension.superscript = arguments.superscript;
+    options.extension.math_dollars = arguments.math;
+    options.render.width = arguments.wrap;
+
+    options
+}
+
+fn create_renderer(arguments: &Opt) -> Result<Renderer, GenericError> {
+    Ok(Renderer {
+        options: create_options(arguments),
+        highlighter: create_highlighter(arguments)?,
+        heading_anchors: arguments.heading_anchors,
+        heading_id_prefix: arguments.heading_id_prefix.clone(),
+        pretty_urls: arguments.pretty_urls,
+        input_directories: arguments.inputs.clone(),
+        encoding: arguments.encoding,
+        extensions: arguments.extensions.clone(),
+        output_extension: arguments.output_extension.clone(),
+        slugify: arguments.slugify,
+        slug_style: slug_style(arguments),
+        title_level: arguments.title_level,
+        excerpt_length: arguments.excerpt_length,
+        format: arguments.format,
+        lazy_images: arguments.lazy_images,
+        code_copy: arguments.code_copy,
+        lint_headings: arguments.lint_headings,
+        strip_comments: arguments.strip_comments,
+        sanitize: arguments.sanitize,
+        images_roots: arguments
+            .inputs
+            .iter()
+            .chain([&arguments.static_dir])
+            .cloned()
+            .collect(),
+        base_path: arguments
+            .base_url
+            .as_deref()
+            .map(links::base_path)
+            .unwrap_or_default()
+            .to_owned(),
+    })
+}
+
+// `pages` are the (source, destination) pairs of every generated page.
+fn write_sitemap(
+    arguments: &Opt,
+    base_url: &str,
+    pages: &[(&PathBuf, ProcessedFile)],
+) -> Result<(), Box<dyn error::Error + 'static>> {
+    let mut entries = Vec::new();
+
+    for (source, ProcessedFile { destination, .. }) in pages {
+        // Not a page of the site as such.
+        if *destination == arguments.output.join(NOT_FOUND_PAGE) {
+            continue;
+        }
+
+        entries.push(sitemap::Entry {
+            url: page_url(
+                arguments,
+                base_url,
+                destination.strip_prefix(&arguments.output)?,
+            ),
+            last_modified: fs::metadata(source)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        });
+    }
+
+    let destination = arguments.output.join(sitemap::SITEMAP_FILE);
+    if arguments.dry_run {
+        info!(
+            "Dry run: would write sitemap to '{}'.",
+            destination.display()
+        );
+        return Ok(());
+    }
+
+    info!("Writing sitemap to '{}'.", destination.display());
+
+    sitemap::write(&destination, &mut entries)?;
+
+    Ok(())
+}
+
+fn write_robots(arguments: &Opt) -> io::Result<()> {
+    let destination = arguments.output.join(robots::ROBOTS_FILE);
+    if arguments.dry_run {
+        info!(
+            "Dry run: would write robots.txt to '{}'.",
+            destination.display()
+        );
+        return Ok(());
+    }
+
+    info!("Writing robots.txt to '{}'.", destination.display());
+
+    let sitemap = arguments
+        .base_url
+        .as_ref()
+        .map(|base_url| links::absolute_url(base_url, Path::new(sitemap::SITEMAP_FILE)));
+
+    robots::write(&destination, &arguments.robots_disallow, sitemap.as_deref())
+}
+
+// For --default-404, using the templates as any other page. Returns the title of the page.
+fn write_default_not_found_page(
+    arguments: &Opt,
+    templates: &Templates,
+    listings: Option<&Listings>,
+    fingerprints: &Fingerprints,
+) -> Result<String, GenericError> {
+    let home = format!(
+        "{}/",
+        arguments
+            .base_url
+            .as_deref()
+            .map(links::base_path)
+            .unwrap_or_default()
+    );
+
+    let page = ProcessedFile {
+        destination: arguments.output.join(NOT_FOUND_PAGE),
+        skipped: false,
+        data: FileData {
+            html_content: format!(
+                "<h1>Page not found</h1>\n<p>This page doesn't exist. <a href=\"{}\">Go to the home page</a>.</p>\n",
+                sitemap::escape_xml(&home)
+            ),
+            title: "Page not found".to_owned(),
+            ..FileData::default()
+        },
+    };
+
+    let source = arguments.input.join("404.md");
+    write_page(arguments, &source, &page, templates, listings, fingerprints)?;
+
+    Ok(page.data.title)
+}
+
+// Writes every page to `single_file`, relative to the output directory, in the order they are
+// listed. The templates are those of the input's `index.md`.
+fn write_single_file(
+    arguments: &Opt,
+    single_file: &Path,
+    pages: &[(&PathBuf, ProcessedFile)],
+    templates: &Templates,
+    listings: Option<&Listings>,
+    fingerprints: &Fingerprints,
+) -> Result<(), GenericError> {
+    let sections: Vec<single::Section> = pages
+        .iter()
+        .filter_map(|(_, page)| {
+            Some(single::Section {
+                destination: page.destination.strip_prefix(&arguments.output).ok()?,
+                title: &page.data.title,
+                html: &page.data.html_content,
+            })
+        })
+        .collect();
+
+    let page = ProcessedFile {
+        destination: arguments.output.join(single_file),
+        skipped: false,
+        data: FileData {
+            html_content: single::combine(
+                &sections,
+                single_file,
+                &index_page_name(arguments),
+                slug_style(arguments),
+            ),
+            title: humanize_file_name(single_file),
+            ..FileData::default()
+        },
+    };
+
+    info!("Writing every page to '{}'.", page.destination.display());
+    let source = arguments.input.join("index.md");
+    write_page(arguments, &source, &page, templates, listings, fingerprints)
+}
+
+// Writes `tags/<tag>.html` for every frontmatter tag, listing its pages, and `tags/index.html`
+// listing the tags, where a page of the input would be written. They use the templates as any
+// other page. Returns the number of pages which
+// could not be written and records the (destination, title) of the others in `generated`.
+fn write_tag_pages(
+    arguments: &Opt,
+    pages: &[(&PathBuf, ProcessedFile)],
+    templates: &Templates,
+    listings: Option<&Listings>,
+    fingerprints: &Fingerprints,
+    generated: &mut Vec<(PathBuf, Option<String>)>,
+) -> usize {
+    let tagged = tags::group(pages.iter().filter_map(|(_, page)| {
+        let tags = page.data.metadata.get("tags")?;
+        let path = page.destination.strip_prefix(&arguments.output).ok()?;
+
+        if is_draft(&page.data) {
+            return None;
+        }
+
+        let tagged_page = tags::Page {
+            path,
+            title: &page.data.title,
+            excerpt: &page.data.excerpt,
+        };
+        Some((tagged_page, tags::parse(tags)))
+    }));
+
+    if tagged.is_empty() {
+        return 0;
+    }
+
+    let paths = tags::tag_paths(
+        tagged.keys(),
+        slug_style(arguments),
+        &arguments.output_extension,
+        arguments.pretty_urls,
+    );
+    let index_path = tags::index_path(&arguments.output_extension);
+    let mut tag_pages = vec![(
+        index_path.clone(),
+        "Tags".to_owned(),
+        tags::render_index(&tagged, &paths, &index_path),
+    )];
+    tag_pages.extend(tagged.iter().map(|(tag, pages)| {
+        let path = paths[tag].clone();
+        let html_content = tags::render_tag(tag, pages, &path);
+        (path, tag.clone(), html_content)
+    }));
+
+    let mut failures = 0;
+
+    for (path, title, html_content) in tag_pages {
+        let page = ProcessedFile {
+            destination: arguments.output.join(&path),
+            skipped: false,
+            data: FileData {
+                html_content,
+                title,
+                ..FileData::default()
+            },
+        };
+
+        let source = arguments.input.join(&path);
+        match write_page(arguments, &source, &page, templates, listings, fingerprints) {
+            Ok(()) => generated.push((page.destination, Some(page.data.title))),
+            Err(error) => {
+                error!(
+                    "Could not write tag page '{}'. Error: {}",
+                    page.destination.display(),
+                    error.message
+                );
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+// Drafts are built but left out of the site-wide listings.
+fn is_draft(file_data: &FileData) -> bool {
+    file_data
+        .metadata
+        .get("draft")
+        .is_some_and(|draft| draft == "true")
+}
+
+// For --frontmatter-only. Urls are as in the search index, the drafts are listed as well.
+fn write_frontmatter_export(
+    arguments: &Opt,
+    pages: &[(&PathBuf, ProcessedFile)],
+) -> Result<(), Box<dyn error::Error + 'static>> {
+    let mut entries = Vec::new();
+
+    for (file, page) in pages {
+        let relative_path = page.destination.strip_prefix(&arguments.output)?;
+        let url = match &arguments.base_url {
+            Some(base_url) => page_url(arguments, base_url, relative_path),
+            None => links::relative_url(Path::new(""), relative_path),
+        };
+        let source = match input_root(arguments, file) {
+            Some(root) => file.strip_prefix(root)?.to_path_buf(),
+            None => file.to_path_buf(),
+        };
+
+        entries.push(export::Entry {
+            source,
+            url,
+            title: page.data.title.clone(),
+            excerpt: page.data.excerpt.clone(),
+            metadata: page.data.metadata.clone().into_iter().collect(),
+        });
+    }
+
+    let destination = arguments.output.join(export::FRONTMATTER_FILE);
+    if arguments.dry_run {
+        info!(
+            "Dry run: would write the frontmatter to '{}'.",
+            destination.display()
+        );
+        return Ok(());
+    }
+
+    fs::create_dir_all(&arguments.output)?;
+    export::write(&destination, &mut entries)?;
+    info!(
+        "Wrote the frontmatter of {} page(s) to '{}'.",
+        entries.len(),
+        destination.display()
+    );
+
+    Ok(())
+}
+
+// Urls are absolute with --base-url, relative to the output directory otherwise.
+// Drafts aren't listed.
+fn write_search_index(
+    arguments: &Opt,
+    pages: &[(&PathBuf, ProcessedFile)],
+) -> Result<(), Box<dyn error::Error + 'static>> {
+    let mut entries = Vec::new();
+
+    for (_, page) in pages {
+        if is_draft(&page.data) {
+            continue;
+        }
+
+        let relative_path = page.destination.strip_prefix(&arguments.output)?;
+        let url = match &arguments.base_url {
+            Some(base_url) => page_url(arguments, base_url, relative_path),
+            None => links::relative_url(Path::new(""), relative_path),
+        };
+
+        entries.push(search::Entry {
+            url,
+            title: page.data.title.clone(),
+            text: page.data.text.clone(),
+        });
+    }
+
+    entries.sort_by(|left, right| left.url.cmp(&right.url));
+
+    let destination = arguments.output.join(search::SEARCH_INDEX_FILE);
+    if arguments.dry_run {
+        info!(
+            "Dry run: would write search index to '{}'.",
+            destination.display()
+        );
+        return Ok(());
+    }
+
+    info!("Writing search index to '{}'.", destination.display());
+
+    search::write(&destination, &entries)?;
+
+    Ok(())
+}
+
+// Lists the pages dated by their frontmatter `date` (or modification time), excluding drafts.
+// The feed is named after the site's root `index.md` title, or its url when there is none.
+fn write_feed(
+    arguments: &Opt,
+    base_url: &str,
+    pages: &[(&PathBuf, ProcessedFile)],
+) -> Result<(), Box<dyn error::Error + 'static>> {
+    let mut items = Vec::new();
+    let mut site_title = base_url.to_owned();
+
+    for (source, page) in pages {
+        let relative_path = page.destination.strip_prefix(&arguments.output)?;
+
+        if relative_path == Path::new(&index_page_name(arguments)) {
+            site_title = page.data.title.clone();
+        }
+
+        if is_draft(&page.data) {
+            continue;
+        }
+
+        let date = match page.data.metadata.get("date") {
+            Some(date) => frontmatter::parse_date(date).or_else(|| {
+                warn!(
+                    kind = WarningKind::Frontmatter, source = source;
+                    "Invalid date '{}' in file '{}', using its modification time instead.",
+                    date,
+                    source.display()
+                );
+                None
+            }),
+            None => None,
+        };
+
+        let date = match date.or_else(|| modified_time(source).map(DateTime::<Utc>::from)) {
+            Some(date) => date,
+            None => continue,
+        };
+
+        items.push(feed::Item {
+            title: page.data.title.clone(),
+            url: page_url(arguments, base_url, relative_path),
+            date,
+            excerpt: page.data.excerpt.clone(),
+        });
+    }
+
+    let destination = arguments.output.join(feed::FEED_FILE);
+
+    if arguments.dry_run {
+        info!("Dry run: would write feed to '{}'.", destination.display());
+        return Ok(());
+    }
+
+    info!("Writing feed to '{}'.", destination.display());
+
+    let channel = feed::Channel {
+        title: &site_title,
+        url: base_url,
+    };
+
+    feed::write(&destination, &channel, &mut items, arguments.feed_limit)?;
+
+    Ok(())
+}
+
+fn is_glob_pattern(line: &str) -> bool {
+    line.contains(['*', '?', '['])
+}
+
+// A line of the assets list is either an exact path or a glob pattern such as `images/*.png`.
+// Blank lines and lines starting with `#` are skipped before reaching this point.
+// Relative paths and patterns are both resolved from the input directory.
+fn expand_asset_line(arguments: &Opt, line: &str) -> Vec<PathBuf> {
+    let pattern = arguments.input.join(line);
+
+    if !is_glob_pattern(line) {
+        // A missing asset stays in the list so that copying it reports the failure.
+        let absolute = pattern
+            .canonicalize()
+            .or_else(|_| path::absolute(&pattern))
+            .unwrap_or(pattern);
+
+        return vec![absolute];
+    }
+
+    let matches = match glob::glob(&pattern.to_string_lossy()) {
+        Ok(paths) => paths,
+        Err(error) => {
+            warn!(kind = WarningKind::Asset; "Invalid asset pattern '{}'. Error: {}", line, error);
+            return Vec::new();
+        }
+    };
+
+    let assets: Vec<PathBuf> = matches
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file())
+        .collect();
+
+    if assets.is_empty() {
+        warn!(kind = WarningKind::Asset; "Asset pattern '{}' did not match any file.", line);
+    }
+
+    assets
+}
+
+// The site's own files (templates, configuration) are never published, neither is anything
+// hidden such as `.git`, nor the output directory when it sits inside the input.
+fn is_adjacent_asset(arguments: &Opt, file: &Path) -> bool {
+    const SITE_FILES: [&str; 3] = [HEADER_FILE, FOOTER_FILE, config::CONFIG_FILE];
+
+    if file.starts_with(&arguments.output) || file == arguments.assets_file {
+        return false;
+    }
+
+    let relative = match input_root(arguments, file).map(|root| file.strip_prefix(root)) {
+        Some(Ok(relative)) => relative,
+        _ => return false,
+    };
+
+    let hidden = relative
+        .components()
+        .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+
+    let site_file = file
+        .file_name()
+        .is_some_and(|name| SITE_FILES.iter().any(|site_file| name == *site_file));
+
+    !hidden && !site_file
+}
+
+// Assets of the input directory keep their place in the output, those of the --static-dir their
+// place within it. Those outside of it, such as
+// `/usr/share/logos/logo.png`, are copied to the --external-assets directory of the output,
+// under their file name.
+fn asset_destination(arguments: &Opt, asset: &Path) -> Result<PathBuf, GenericError> {
+    // `static/css/app.css` is copied to `css/app.css`.
+    if let Ok(relative) = asset.strip_prefix(&arguments.static_dir) {
+        return Ok(arguments.output.join(relative));
+    }
+
+    if input_root(arguments, asset).is_some() {
+        let destination = destination_for_file(arguments, asset)
+            .map_err(|error| GenericError::new(error.to_string()))?;
+
+        return match arguments.slugify {
+            true => slugify_destination(arguments, &destination),
+            false => Ok(destination),
+        };
+    }
+
+    match asset.file_name() {
+        Some(name) => Ok(arguments.output.join(&arguments.external_assets).join(name)),
+        None => Err(GenericError::new(format!(
+            "'{}' is not a file.",
+            asset.display()
+        ))),
+    }
+}
+
+fn copy_asset(
+    arguments: &Opt,
+    fingerprints: &Fingerprints,
+    asset: &Path,
+) -> Result<PathBuf, Box<dyn error::Error + 'static>> {
+    let mut destination = asset_destination(arguments, asset)?;
+
+    if let Some(fingerprinted) = destination
+        .strip_prefix(&arguments.output)
+        .ok()
+        .and_then(|relative| fingerprints.path(relative))
+    {
+        destination = arguments.output.join(fingerprinted);
+    }
+
+    debug!(
+        "Copying '{}'\n \tto '{}'.",
+        asset.display(),
+        destination.display()
+    );
+
+    if arguments.dry_run {
+        info!(
+            "Dry run: would copy '{}' to '{}'.",
+            asset.display(),
+            destination.display()
+        );
+        return Ok(destination);
+    }
+
+    create_output_file_path(arguments, &destination)?;
+    // The permissions, and so the executable bit, are copied along with the content.
+    fs::copy(asset, &destination)?;
+
+    if arguments.preserve_timestamps {
+        let modified = fs::metadata(asset)?.modified()?;
+        File::options()
+            .write(true)
+            .open(&destination)?
+            .set_modified(modified)?;
+    }
+
+    if arguments.compress && compress::is_compressible(&destination) {
+        compress::write_gzip(&destination, arguments.compression_level)?;
+    }
+
+    Ok(destination)
+}
+
+// Hashes the fingerprinted assets among `lists`, for --fingerprint. An asset which can't be read
+// keeps its name, copying it reports the problem.
+fn fingerprint_assets(
+    arguments: &Opt,
+    pool: &rayon::ThreadPool,
+    lists: &[&[PathBuf]],
+) -> Fingerprints {
+    let hashed: Vec<(PathBuf, String)> = pool.install(|| {
+        lists
+            .par_iter()
+            .flat_map(|assets| assets.par_iter())
+            .filter(|asset| fingerprint::is_fingerprinted(asset))
+            .filter_map(|asset| {
+                let destination = asset_destination(arguments, asset).ok()?;
+                let relative = destination.strip_prefix(&arguments.output).ok()?;
+
+                let content = fs::read(asset).ok()?;
+
+                Some((relative.to_path_buf(), fingerprint::hash(&content)))
+            })
+            .collect()
+    });
+
+    let mut fingerprints = Fingerprints::default();
+    for (relative, hash) in hashed {
+        fingerprints.insert(relative, hash);
+    }
+
+    fingerprints
+}
+
+// Copies every asset, even when some of them fail. Returns the number of failures and records the
+// (source, destination) of each copied asset in `copied`. The copies are independent and run on
+// the pool's threads, the failures are reported once they are all done, in the order of `assets`.
+fn copy_assets(
+    arguments: &Opt,
+    pool: &rayon::ThreadPool,
+    fingerprints: &Fingerprints,
+    assets: &[PathBuf],
+    copied: &mut Vec<(PathBuf, PathBuf)>,
+) -> usize {
+    let mut failures = 0;
+
+    // Two assets can share a destination, as two `logo.png` from outside the input directories
+    // which are both copied to --external-assets. The first one listed is copied, the others are
+    // reported. An asset listed twice is copied once.
+    let mut destinations: HashMap<PathBuf, &PathBuf> = HashMap::new();
+    let mut unique = Vec::with_capacity(assets.len());
+    for asset in assets {
+        // Copying it reports the problem.
+        let destination = match asset_destination(arguments, asset) {
+            Ok(destination) => destination,
+            Err(_) => {
+                unique.push(asset.clone());
+                continue;
+            }
+        };
+
+        match destinations.get(&destination) {
+            Some(first) if *first == asset => {}
+            Some(first) => {
+                error!(
+                    "Could not copy asset '{}'. Error: output file '{}' is also copied from '{}'.",
+                    asset.display(),
+                    destination.display(),
+                    first.display()
+                );
+                failures += 1;
+            }
+            None => {
+                destinations.insert(destination, asset);
+                unique.push(asset.clone());
+            }
+        }
+    }
+    let assets = unique;
+
+    // Every copy is shown with --verbose.
+    if !assets.is_empty() {
+        info!("Copying {} asset(s)...", assets.len());
+    }
+
+    let results: Vec<Result<PathBuf, String>> = pool.install(|| {
+        assets
+            .par_iter()
+            .map(|asset| {
+                copy_asset(arguments, fingerprints, asset).map_err(|error| error.to_string())
+            })
+            .collect()
+    });
+
+    for (asset, result) in assets.iter().zip(results) {
+        match result {
+            Ok(destination) => {
+                copied.push((asset.clone(), destination));
+            }
+            Err(error) => {
+                error!(
+                    "Could not copy asset '{}'. Error: {}",
+                    asset.display(),
+                    error
+                );
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+// Checks the relative links of every page point to a page or an asset of this build.
+// Returns the number of broken links.
+fn check_links(
+    arguments: &Opt,
+    pages: &[(&PathBuf, ProcessedFile)],
+    published: &HashSet<PathBuf>,
+) -> usize {
+    let mut broken = 0;
+    let index = index_page_name(arguments);
+
+    for (source, page) in pages {
+        let relative_destination = page
+            .destination
+            .strip_prefix(&arguments.output)
+            .unwrap_or(&page.destination);
+
+        for url in &page.data.links {
+            let exists =
+                links::resolve_link(relative_destination, url, &index).is_some_and(|target| {
+                    let target = arguments.output.join(target);
+                    published.contains(&target) || published.contains(&target.join(&index))
+                });
+
+            if !exists {
+                warn!(kind = WarningKind::BrokenLink, source = source; "Broken link '{}' in file '{}'.", url, source.display());
+                broken += 1;
+            }
+        }
+    }
+
+    broken
+}
+
+// Lists every file of the build, which are read back from the output directory: the pages, the
+// site-wide files of `generated` from `write_site_files`, the assets and their gzip siblings.
+fn write_manifest(
+    arguments: &Opt,
+    manifest: &Path,
+    pages: &[(&PathBuf, ProcessedFile)],
+    generated: &[(PathBuf, Option<String>)],
+    copied: &[(PathBuf, PathBuf)],
+) -> io::Result<()> {
+    let destination = arguments.output.join(manifest);
+    if arguments.dry_run {
+        info!(
+            "Dry run: would write manifest to '{}'.",
+            destination.display()
+        );
+        return Ok(());
+    }
+
+    let source = |file: &Path| match input_root(arguments, file) {
+        Some(root) => Some(file.strip_prefix(root).unwrap_or(file).to_path_buf()),
+        None => Some(file.to_path_buf()),
+    };
+
+    let mut entries = Vec::new();
+    // With --compress the pages and the text assets have a `.gz` next to them, from the same
+    // source. That of an up-to-date page may not have been written yet.
+    let mut push = |destination: &Path,
+                    source: Option<PathBuf>,
+                    title: Option<String>,
+                    compressed: bool|
+     -> io::Result<()> {
+        let gzip = compress::gzip_path(destination);
+        if compressed && arguments.compress && gzip.exists() {
+            entries.push(manifest::entry(
+                &arguments.output,
+                &gzip,
+                source.clone(),
+                None,
+            )?);
+        }
+
+        entries.push(manifest::entry(
+            &arguments.output,
+            destination,
+            source,
+            title,
+        )?);
+        Ok(())
+    };
+
+    // The pages are all in it, it is generated from the whole input.
+    if let Some(single_file) = &arguments.single_file {
+        push(
+            &arguments.output.join(single_file),
+            Some(PathBuf::from(".")),
+            None,
+            true,
+        )?;
+    }
+
+    let pages = match arguments.single_file {
+        Some(_) => &[],
+        None => pages,
+    };
+    for (file, page) in pages {
+        push(
+            &page.destination,
+            source(file),
+            Some(page.data.title.clone()),
+            true,
+        )?;
+    }
+
+    // The tag pages and the default 404 are written as the pages are, the other files aren't
+    // compressed.
+    for (destination, title) in generated {
+        push(destination, None, title.clone(), title.is_some())?;
+    }
+
+    for (asset, copied) in copied {
+        push(
+            copied,
+            source(asset),
+            None,
+            compress::is_compressible(copied),
+        )?;
+    }
+
+    info!("Writing manifest to '{}'.", destination.display());
+
+    manifest::write(&destination, &mut entries)
+}
+
+// Writes the tag pages, the sitemap, the feed and the search index, returning the problems met.
+// The (destination, title) of every file written is recorded in `generated`, the title being
+// that of the pages.
+fn write_site_files(
+    arguments: &Opt,
+    pages: &[(&PathBuf, ProcessedFile)],
+    templates: &Templates,
+    listings: Option<&Listings>,
+    fingerprints: &Fingerprints,
+    generated: &mut Vec<(PathBuf, Option<String>)>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let tag_failures = write_tag_pages(
+        arguments,
+        pages,
+        templates,
+        listings,
+        fingerprints,
+        generated,
+    );
+    if tag_failures > 0 {
+        problems.push(format!("{} tag page(s) could not be written", tag_failures));
+    }
+
+    let not_found = arguments.output.join(NOT_FOUND_PAGE);
+    if arguments.default_404 && !pages.iter().any(|(_, page)| page.destination == not_found) {
+        match write_default_not_found_page(arguments, templates, listings, fingerprints) {
+            Ok(title) => generated.push((not_found, Some(title))),
+            Err(error) => {
+                error!(
+                    "Could not write {}. Error: {}",
+                    NOT_FOUND_PAGE, error.message
+                );
+                problems.push(format!("{} could not be written", NOT_FOUND_PAGE));
+            }
+        }
+    }
+
+    // Both the sitemap and the feed require absolute urls.
+    match &arguments.base_url {
+        Some(base_url) => {
+            match write_sitemap(arguments, base_url, pages) {
+                Ok(()) => generated.push((arguments.output.join(sitemap::SITEMAP_FILE), None)),
+                Err(error) => {
+                    error!(
+                        "Could not write {}. Error: {}",
+                        sitemap::SITEMAP_FILE,
+                        error
+                    );
+                    problems.push(format!("{} could not be written", sitemap::SITEMAP_FILE));
+                }
+            }
+
+            match write_feed(arguments, base_url, pages) {
+                Ok(()) => generated.push((arguments.output.join(feed::FEED_FILE), None)),
+                Err(error) => {
+                    error!("Could not write {}. Error: {}", feed::FEED_FILE, error);
+                    problems.push(format!("{} could not be written", feed::FEED_FILE));
+                }
+            }
+        }
+        None => info!(
+            "No --base-url given, skipping {} and {}.",
+            sitemap::SITEMAP_FILE,
+            feed::FEED_FILE
+        ),
+    }
+
+    if arguments.robots || !arguments.robots_disallow.is_empty() {
+        match write_robots(arguments) {
+            Ok(()) => generated.push((arguments.output.join(robots::ROBOTS_FILE), None)),
+            Err(error) => {
+                error!("Could not write {}. Error: {}", robots::ROBOTS_FILE, error);
+                problems.push(format!("{} could not be written", robots::ROBOTS_FILE));
+            }
+        }
+    }
+
+    if !arguments.no_search_index {
+        match write_search_index(arguments, pages) {
+            Ok(()) => generated.push((arguments.output.join(search::SEARCH_INDEX_FILE), None)),
+            Err(error) => {
+                error!(
+                    "Could not write {}. Error: {}",
+                    search::SEARCH_INDEX_FILE,
+                    error
+                );
+                problems.push(format!(
+                    "{} could not be written",
+                    search::SEARCH_INDEX_FILE
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+// Runs a full build: converts every markdown file of the input directories, writes the site-wide
+// files and copies the assets. The `arguments` come from `configure` or `Opt::new`, they are
+// checked and their paths resolved first when they aren't already (`webmark.toml` is only read by
+// `configure`). Fails when anything couldn't be written, once everything else is, the error still
+// carrying the report of what was done.
+pub fn build(arguments: &Opt) -> Result<BuildReport, BuildError> {
+    let arguments = &normalize(arguments).map_err(|error| BuildError {
+        report: BuildReport::default(),
+        source: Box::new(error),
+    })?;
+    log::set_strict(arguments.strict);
+
+    // What this build reports, whatever other builds run at the same time.
+    let collector = Arc::new(log::Collector::default());
+    let collecting = log::collect_into(&collector);
+
+    let mut report = BuildReport::default();
+    let result = build_site(arguments, &mut report, &collector);
+    drop(collecting);
+
+    report.warnings = collector.take_warnings();
+    report.errors = collector.counts().1;
+
+    match result {
+        Ok(()) => Ok(report),
+        Err(source) => Err(BuildError { report, source }),
+    }
+}
+
+// The steps of `build`, which fills the counts of `report` as they are known. The threads of its
+// pool report to `collector` as well.
+fn build_site(
+    arguments: &Opt,
+    report: &mut BuildReport,
+    collector: &Arc<log::Collector>,
+) -> Result<(), Box<dyn error::Error + 'static>> {
+    let start = Instant::now();
+
+    let exclusions = Exclusions::new(&arguments.inputs, &arguments.exclude)?;
+    let mut walk = Walk::new(arguments, &exclusions);
+    let mut files: Vec<PathBuf> = arguments
+        .inputs
+        .iter()
+        .flat_map(|input| list_markdown_files(input, &mut walk))
+        .collect();
+    files.sort_by(|left, right| compare_paths(left, right));
+
+    // The assets are still copied. As any warning, this fails the build with --strict.
+    if files.is_empty() {
+        let inputs: Vec<String> = arguments
+            .inputs
+            .iter()
+            .map(|input| format!("'{}'", input.display()))
+            .collect();
+        warn!(kind = WarningKind::NoPages; "No markdown files found under {}.", inputs.join(", "));
+    }
+
+    walk.others
+        .sort_by(|left, right| compare_paths(left, right));
+    let templates = read_templates(arguments);
+    let renderer = create_renderer(arguments)?;
+
+    let pool_collector = Arc::clone(collector);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(arguments.jobs.unwrap_or(0))
+        .start_handler(move |_| log::attach(&pool_collector))
+        .build()?;
+
+    // Each file is converted independently; failures are collected so that one bad
+    // page doesn't prevent the rest of the site from being generated.
+    let results: Vec<(&PathBuf, Result<Option<ProcessedFile>, GenericError>)> =
+        pool.install(|| {
+            files
+                .par_iter()
+                .map(|file| {
+                    let result = read_page(arguments, file, &templates, &renderer);
+
+                    (file, result)
+                })
+                .collect()
+        });
+
+    // The markdown files included by the pages are parts of them rather than pages of their own.
+    let partials: HashSet<PathBuf> = results
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().ok()?.as_ref())
+        .flat_map(|processed| processed.data.includes.iter().cloned())
+        .collect();
+    report.partials = partials.iter().cloned().collect();
+    report.partials.sort();
+    let is_partial = |file: &Path| {
+        file.canonicalize()
+            .is_ok_and(|canonical| partials.contains(&canonical))
+    };
+
+    let mut read = Vec::new();
+    let mut failures = Vec::new();
+
+    // Two sources can share a destination, as `My Page.md` and `my-page.md` with --slugify.
+    // The first one listed is written, the others are reported or, with --warn-duplicates, skipped.
+    let mut destinations: HashMap<PathBuf, &PathBuf> = HashMap::new();
+
+    for (file, result) in results {
+        if is_partial(file) {
+            debug!(
+                "Not writing '{}', it is included by other pages.",
+                file.display()
+            );
+            continue;
+        }
+
+        let processed = match result {
+            Ok(Some(processed)) => processed,
+            Ok(None) => continue,
+            Err(error) => {
+                failures.push((file, error));
+                continue;
+            }
+        };
+
+        match destinations.get(&processed.destination) {
+            Some(first) if arguments.warn_duplicates => warn!(
+                kind = WarningKind::Skipped, source = file;
+                "Skipping '{}', output file '{}' is also generated from '{}'.",
+                file.display(),
+                processed.destination.display(),
+                first.display()
+            ),
+            Some(first) => failures.push((
+                file,
+                GenericError::new(format!(
+                    "Output file '{}' is also generated from '{}'.",
+                    processed.destination.display(),
+                    first.display()
+                )),
+            )),
+            None => {
+                destinations.insert(processed.destination.clone(), file);
+                read.push((file, processed));
+            }
+        }
+    }
+
+    if arguments.strict && !failures.is_empty() {
+        for (file, error) in &failures {
+            error!(
+                "Could not process file '{}'. Error: {}",
+                file.display(),
+                error.message
+            );
+        }
+
+        return Err(Box::new(GenericError::new(format!(
+            "{} out of {} file(s) could not be processed, nothing was written.",
+            failures.len(),
+            files.len()
+        ))));
+    }
+
+    // The pages are read and nothing else is done, the failures are reported as usual.
+    if arguments.frontmatter_only {
+        let mut problems = Vec::new();
+        if let Err(error) = write_frontmatter_export(arguments, &read) {
+            problems.push(format!("the frontmatter could not be written ({})", error));
+        }
+
+        for (file, error) in &failures {
+            error!(
+                "Could not process file '{}'. Error: {}",
+                file.display(),
+                error.message
+            );
+        }
+        if !failures.is_empty() {
+            problems.insert(
+                0,
+                format!(
+                    "{} out of {} file(s) could not be processed",
+                    failures.len(),
+                    files.len()
+                ),
+            );
+        }
+
+        report.skipped = read.len();
+
+        let (warnings, _) = collector.counts();
+        if arguments.strict && warnings > 0 {
+            problems.push(format!("{} warning(s) with --strict", warnings));
+        }
+
+        if !problems.is_empty() {
+            return Err(Box::new(GenericError::new(format!(
+                "{}.",
+                problems.join(", ")
+            ))));
+        }
+
+        return Ok(());
+    }
+
+    // The links were rewritten for where the sources put the pages, before the permalinks of
+    // the other pages are known.
+    let moved: HashMap<PathBuf, PathBuf> = read
+        .iter()
+        .filter_map(|(file, page)| {
+            let computed = page_destination(arguments, file).ok()?;
+            let computed = computed.strip_prefix(&arguments.output).ok()?;
+            let destination = page.destination.strip_prefix(&arguments.output).ok()?;
+
+            (computed != destination).then(|| (computed.to_path_buf(), destination.to_path_buf()))
+        })
+        .collect();
+    if !moved.is_empty() {
+        let index = index_page_name(arguments);
+        let permalinks = Permalinks {
+            moved: &moved,
+            index: &index,
+        };
+
+        for (file, page) in read.iter_mut() {
+            let (Ok(computed), Ok(destination)) = (
+                page_destination(arguments, file),
+                page.destination.strip_prefix(&arguments.output),
+            ) else {
+                continue;
+            };
+            let computed = computed
+                .strip_prefix(&arguments.output)
+                .unwrap_or(&computed);
+            let rewrite = |url: &str| permalinks.rewrite(url, computed, destination);
+
+            // The links of the up-to-date pages are still checked.
+            for link in page.data.links.iter_mut() {
+                if let Some(url) = rewrite(link) {
+                    *link = url;
+                }
+            }
+            if !page.skipped {
+                page.data.html_content = links::rewrite_html_urls(&page.data.html_content, rewrite);
+            }
+        }
+    }
+
+    // Pages are only written once all of them are read, since the navigation lists them all.
+    let listings = templates.lists_pages.then(|| {
+        // With the `order:` of their frontmatter, which sets their place in the sequence.
+        let ordered: Vec<(breadcrumbs::Page, Option<i64>)> = read
+            .iter()
+            .filter_map(|(file, page)| {
+                let listed = breadcrumbs::Page {
+                    source: file.strip_prefix(input_root(arguments, file)?).ok()?,
+                    destination: page.destination.strip_prefix(&arguments.output).ok()?,
+                    title: &page.data.title,
+                };
+
+                let order = page.data.metadata.get("order").and_then(|order| {
+                    let parsed = order.trim().parse().ok();
+                    if parsed.is_none() {
+                        warn!(
+                            kind = WarningKind::Frontmatter, source = file;
+                            "Invalid order '{}' in '{}', expected a whole number.",
+                            order,
+                            file.display()
+                        );
+                    }
+                    parsed
+                });
+
+                Some((listed, order))
+            })
+            .collect();
+
+        let listed: Vec<breadcrumbs::Page> = ordered.iter().map(|(page, _)| *page).collect();
+        let sequence: Vec<(breadcrumbs::Page, Option<i64>)> = ordered
+            .into_iter()
+            .filter(|(page, _)| !is_not_found_page(page.source))
+            .collect();
+
+        Listings {
+            nav: nav::Folder::new(
+                listed
+                    .iter()
+                    .map(|page| (page.source, page.destination, page.title)),
+            ),
+            breadcrumbs: breadcrumbs::Breadcrumbs::new(&listed),
+            pager: pager::Pager::new(&sequence),
+        }
+    });
+
+    // The assets are listed before writing the pages, which point to their fingerprinted names.
+    let listing = match arguments.assets_given || arguments.assets_file.exists() {
+        true => read_file_string(&arguments.assets_file).unwrap_or("".to_owned()),
+        false => String::new(),
+    };
+    let assets: Vec<PathBuf> = listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| expand_asset_line(arguments, line))
+        .collect();
+
+    let adjacent: Vec<PathBuf> = match arguments.copy_adjacent {
+        true => walk
+            .others
+            .into_iter()
+            .filter(|file| is_adjacent_asset(arguments, file))
+            .collect(),
+        false => Vec::new(),
+    };
+
+    let static_files = list_static_files(arguments, &exclusions);
+
+    let fingerprints = match arguments.fingerprint {
+        true => fingerprint_assets(arguments, &pool, &[&assets, &adjacent, &static_files]),
+        false => Fingerprints::default(),
+    };
+
+    // Every step is attempted even when a previous one failed, the problems are reported at the end.
+    let mut problems = Vec::new();
+
+    let written: Vec<Result<(), GenericError>> = match &arguments.single_file {
+        Some(single_file) => {
+            let result = write_single_file(
+                arguments,
+                single_file,
+                &read,
+                &templates,
+                listings.as_ref(),
+                &fingerprints,
+            );
+            if let Err(error) = result {
+                problems.push(format!(
+                    "the single file could not be written ({})",
+                    error.message
+                ));
+            }
+
+            read.iter().map(|_| Ok(())).collect()
+        }
+        None => pool.install(|| {
+            read.par_iter()
+                .map(|(file, page)| {
+                    if page.skipped {
+                        return Ok(());
+                    }
+
+                    write_page(
+                        arguments,
+                        file,
+                        page,
+                        &templates,
+                        listings.as_ref(),
+                        &fingerprints,
+                    )
+                })
+                .collect()
+        }),
+    };
+
+    let mut pages = Vec::new();
+    let mut skipped = 0;
+
+    for ((file, page), result) in read.into_iter().zip(written) {
+        match result {
+            Ok(()) => {
+                if page.skipped {
+                    skipped += 1;
+                }
+
+                pages.push((file, page));
+            }
+            Err(error) => failures.push((file, error)),
+        }
+    }
+
+    if skipped > 0 {
+        info!(
+            "Skipped {} up-to-date file(s), use --force to rebuild them.",
+            skipped
+        );
+    }
+
+    // These list html pages, there are none when writing markdown.
+    let mut generated = Vec::new();
+    if arguments.format == Format::Commonmark {
+        info!("Writing markdown, skipping the tag pages and the site-wide files.");
+    } else if arguments.single_file.is_some() {
+        info!("Writing a single file, skipping the tag pages and the site-wide files.");
+    } else {
+        problems.extend(write_site_files(
+            arguments,
+            &pages,
+            &templates,
+            listings.as_ref(),
+            &fingerprints,
+            &mut generated,
+        ));
+    }
+
+    // Copied at once, for a single line of progress. `adjacent` is empty without
+    // --copy-adjacent.
+    let every_asset = [assets.as_slice(), &adjacent, &static_files].concat();
+    let mut copied = Vec::new();
+    let asset_failures = copy_assets(arguments, &pool, &fingerprints, &every_asset, &mut copied);
+    let asset_count = copied.len();
+
+    if asset_failures > 0 {
+        problems.push(format!("{} asset(s) could not be copied", asset_failures));
+    }
+
+    if let Some(manifest) = &arguments.manifest {
+        if let Err(error) = write_manifest(arguments, manifest, &pages, &generated, &copied) {
+            problems.push(format!("the manifest could not be written ({})", error));
+        }
+    }
+
+    let mut published: HashSet<PathBuf> = pages
+        .iter()
+        .map(|(_, page)| page.destination.clone())
+        .chain(copied.into_iter().map(|(_, destination)| destination))
+        .collect();
+
+    // The links are checked as written, before pointing to the fingerprinted assets.
+    if !fingerprints.is_empty() {
+        published.extend(
+            assets
+                .iter()
+                .chain(&adjacent)
+                .chain(&static_files)
+                .filter_map(|asset| asset_destination(arguments, asset).ok()),
+        );
+    }
+
+    if arguments.check_links || arguments.deny_broken_links {
+        for site_file in [
+            sitemap::SITEMAP_FILE,
+            feed::FEED_FILE,
+            robots::ROBOTS_FILE,
+            search::SEARCH_INDEX_FILE,
+        ] {
+            published.insert(arguments.output.join(site_file));
+        }
+
+        let broken = check_links(arguments, &pages, &published);
+
+        if broken > 0 && arguments.deny_broken_links {
+            problems.push(format!("{} broken link(s)", broken));
+        } else if broken > 0 {
+            warn!(kind = WarningKind::BrokenLink; "Found {} broken link(s).", broken);
+        } else {
+            info!("No broken link found.");
+        }
+    }
+
+    if !failures.is_empty() {
+        for (file, error) in &failures {
+            error!(
+                "Could not process file '{}'. Error: {}",
+                file.display(),
+                error.message
+            );
+        }
+
+        problems.insert(
+            0,
+            format!(
+                "{} out of {} file(s) could not be processed",
+                failures.len(),
+                files.len()
+            ),
+        );
+    }
+
+    let (warnings, errors) = collector.counts();
+    if arguments.strict && warnings > 0 {
+        problems.push(format!("{} warning(s) with --strict", warnings));
+    }
+
+    info!(
+        "Built in {:.2}s: {} page(s) converted, {} skipped, {} asset(s) copied, {} warning(s), {} error(s).",
+        start.elapsed().as_secs_f64(),
+        pages.len() - skipped,
+        skipped,
+        asset_count,
+        warnings,
+        errors
+    );
+
+    report.converted = pages.len() - skipped;
+    report.skipped = skipped;
+    report.permalinks = moved.len();
+    report.assets = asset_count;
+
+    if !problems.is_empty() {
+        return Err(Box::new(GenericError::new(format!(
+            "{}.",
+            problems.join(", ")
+        ))));
+    }
+
+    Ok(())
+}
+
+// What a build did, for the programs using the library. The warnings are also shown as the build
+// goes, as for the command line.
+#[derive(Clone, Debug, Default)]
+pub struct BuildReport {
+    pub converted: usize,
+    pub skipped: usize,
+    // Pages written at the `permalink:` of their frontmatter.
+    pub permalinks: usize,
+    // Canonical paths of the markdown files included by the pages, which aren't written.
+    pub partials: Vec<PathBuf>,
+    pub assets: usize,
+    pub warnings: Vec<Warning>,
+    pub errors: usize,
+}
+
+// A build which failed, with the report of what it did nonetheless: the pages and assets which
+// were written and the warnings until then.
+#[derive(Debug)]
+pub struct BuildError {
+    pub report: BuildReport,
+    pub source: Box<dyn error::Error + 'static>,
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl Opt {
+    // The default settings, those of the command line without any flag, building the site of
+    // `input` to `output`. Relative paths are resolved from the current directory by `build`:
+    //
+    //     let options = rs_webmark::Opt::new("docs", "public")
+    //         .with_settings("pretty-urls = true\nbase-url = \"https://example.com/\"")?;
+    //     let report = rs_webmark::build(&options)?;
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Opt {
+        let mut options = Opt::from_iter(["rs-webmark"]);
+        options.inputs = vec![input.into()];
+        options.output = output.into();
+        options
+    }
+
+    // Adds an input directory, as a repeated --input-directory.
+    pub fn with_input(mut self, input: impl Into<PathBuf>) -> Opt {
+        self.inputs.push(input.into());
+        self.normalized = false;
+        self
+    }
+
+    // Applies `settings` written as in `webmark.toml`, with the same names as the command line
+    // flags. They replace those set before. Relative paths are resolved from the current directory.
+    pub fn with_settings(mut self, settings: &str) -> Result<Opt, GenericError> {
+        Config::parse(settings)?.merge_into(&mut self, &|_| false, Path::new(""));
+        self.normalized = false;
+        Ok(self)
+    }
+}
+
+// Reads the settings as the command line does, `args` starting with the program name, merges
+// those of `webmark.toml` and checks them. The result is ready for `build`:
+//
+//     let options = rs_webmark::configure(["webmark", "--input-directory", "docs"])?;
+//     let report = rs_webmark::build(&options)?;
+//
+// `--help` and `--version` print their text and exit, as on the command line.
+pub fn configure<I, T>(args: I) -> Result<Opt, GenericError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = Opt::clap()
+        .get_matches_from_safe(args)
+        .map_err(|error| match error.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => error.exit(),
+            _ => GenericError::new(error.message),
+        })?;
+    let options = Opt::from_clap(&matches);
+    log::init(options.verbose, options.quiet);
+
+    let arguments = normalize_program_arguments(&options, &matches)?;
+    log::set_strict(arguments.strict);
+
+    Ok(arguments)
+}
+
+// The command line program: `configure` from the program's arguments, then `build` once, or keep
+// watching and serving the output.
+pub fn run() -> Result<(), Box<dyn error::Error + 'static>> {
+    let arguments = configure(std::env::args_os()).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    if arguments.clean {
+        clean_output_directory(&arguments)?;
+    }
+
+    let serving = arguments
+        .serve
+        .map(|port| port.unwrap_or(serve::DEFAULT_PORT));
+
+    if arguments.open && serving.is_none() {
+        warn!("--open has no effect without --serve.");
+    }
+
+    if !arguments.watch && serving.is_none() {
+        // Any failure makes the exit status non-zero, once everything else has been built.
+        if let Err(error) = build(&arguments) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let listener = serving.map(serve::bind).transpose()?;
+
+    // In watch or serve mode a failed build is reported but shouldn't prevent watching for the
+    // fix, or previewing the rest of the site.
+    let report = match build(&arguments) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("{}", error);
+            error.report
+        }
+    };
+
+    // The server is listening already, the browser's request waits for it to answer.
+    let address = listener.as_ref().map(TcpListener::local_addr);
+    if let (true, Some(Ok(address))) = (arguments.open, address) {
+        serve::open_browser(address.port());
+    }
+
+    match (arguments.watch, listener) {
+        (true, Some(listener)) => {
+            let output = arguments.output.clone();
+            let index = index_page_name(&arguments);
+            thread::spawn(move || serve::serve(listener, &output, &index));
+
+            watch::watch(&arguments, &report)
+        }
+        (true, None) => watch::watch(&arguments, &report),
+        (false, Some(listener)) => {
+            serve::serve(listener, &arguments.output, &index_page_name(&arguments));
+            Ok(())
+        }
+        (false, None) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty directory of its own for every test, the tests running in parallel.
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("rs-webmark-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn test_arguments(input: &Path, extra: &[&str]) -> Opt {
+        let input = input.to_string_lossy().into_owned();
+        let output = Path::new(&input).join("out").to_string_lossy().into_owned();
+        let mut args = vec!["rs-webmark", "-q", "--input-directory", &input];
+        args.extend(&["--output-directory", &output]);
+        args.extend(extra);
+
+        configure(args).unwrap()
+    }
+
+    #[test]
+    fn settings_made_by_the_library_are_normalized_by_build() {
+        let input = test_directory("library-settings");
+        fs::write(input.join("page.md"), "# Page\n").unwrap();
+
+        let options = Opt::new(&input, input.join("public"))
+            .with_settings("pretty-urls = true")
+            .unwrap();
+        let report = build(&options).unwrap();
+
+        assert_eq!(report.converted, 1);
+        assert!(input.join("public/page/index.html").exists());
+
+        let missing = Opt::new(input.join("missing"), input.join("public"));
+        assert!(build(&missing).is_err());
+    }
+
+    #[test]
+    fn builds_running_together_keep_their_own_warnings() {
+        // Every other site has no page, which is a warning.
+        let builds: Vec<_> = (0..8)
+            .map(|index| {
+                let input = test_directory(&format!("own-warnings-{}", index));
+                if index % 2 == 1 {
+                    fs::write(input.join("page.md"), "# Page\n").unwrap();
+                }
+
+                let arguments = test_arguments(&input, &[]);
+                thread::spawn(move || (index, build(&arguments).unwrap()))
+            })
+            .collect();
+
+        for handle in builds {
+            let (index, report) = handle.join().unwrap();
+            let expected = match index % 2 {
+                0 => vec![WarningKind::NoPages],
+                _ => Vec::new(),
+            };
+            let kinds: Vec<WarningKind> =
+                report.warnings.iter().map(|warning| warning.kind).collect();
+
+            assert_eq!(kinds, expected);
+            assert_eq!(report.errors, 0);
+        }
+    }
+
+    #[test]
+    fn pages_of_a_new_directory_are_written_concurrently() {
+        let input = test_directory("concurrent-writes");
+        let arguments = test_arguments(&input, &[]);
+        let directory = arguments.output.join("a/deeply/nested/directory");
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        let results: Vec<_> = pool.install(|| {
+            (0..200)
+                .into_par_iter()
+                .map(|index| {
+                    let file = directory.join(format!("page-{}.html", index));
+                    create_output_file_path(&arguments, &file)
+                        .and_then(|_| fs::write(&file, "").map_err(|error| error.into()))
+                        .map_err(|error| error.to_string())
+                })
+                .collect()
+        });
+
+        for result in results {
+            assert_eq!(result, Ok(()));
+        }
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 200);
+    }
+
+    #[test]
+    fn assets_outside_the_input_are_copied_to_the_external_assets() {
+        let input = test_directory("external-assets");
+        let outside = test_directory("external-assets-outside");
+        let arguments = test_arguments(&input, &["--external-assets", "shared/files"]);
+
+        assert_eq!(
+            asset_destination(&arguments, &outside.join("images/logo.png")).unwrap(),
+            arguments.output.join("shared/files/logo.png")
+        );
+        assert_eq!(
+            asset_destination(&arguments, &input.join("images/logo.png")).unwrap(),
+            arguments.output.join("images/logo.png")
+        );
+    }
+
+    #[test]
+    fn external_assets_of_the_same_name_are_reported() {
+        let input = test_directory("external-duplicates");
+        let outside = test_directory("external-duplicates-outside");
+        fs::create_dir_all(outside.join("first")).unwrap();
+        fs::create_dir_all(outside.join("second")).unwrap();
+        fs::write(outside.join("first/logo.png"), "first").unwrap();
+        fs::write(outside.join("second/logo.png"), "second").unwrap();
+        fs::write(
+            input.join("assets.config"),
+            format!(
+                "{}\n{}\n",
+                outside.join("first/logo.png").display(),
+                outside.join("second/logo.png").display()
+            ),
+        )
+        .unwrap();
+
+        let arguments = test_arguments(&input, &[]);
+        let error = build(&arguments).unwrap_err();
+
+        assert!(
+            error.to_string().contains("1 asset(s) could not be copied"),
+            "{}",
+            error
+        );
+        assert_eq!(error.report.assets, 1);
+        assert_eq!(
+            fs::read_to_string(arguments.output.join("assets/logo.png")).unwrap(),
+            "first"
+        );
+    }
+
+    #[test]
+    fn slugified_pages_point_to_their_slugified_assets() {
+        let input = test_directory("slugify-assets");
+        fs::create_dir_all(input.join("My Notes")).unwrap();
+        fs::write(
+            input.join("My Notes/Day 1.md"),
+            "![photo](<Photo 1.png>) [notes](<Big Notes.pdf#page=2>)\n",
+        )
+        .unwrap();
+        fs::write(input.join("My Notes/Photo 1.png"), "").unwrap();
+        fs::write(input.join("My Notes/Big Notes.pdf"), "").unwrap();
+
+        for extra in [&["--slugify"][..], &["--slugify", "--pretty-urls"][..]] {
+            let mut extra = extra.to_vec();
+            extra.push("--copy-adjacent");
+            let arguments = test_arguments(&input, &extra);
+            build(&arguments).unwrap();
+
+            let page = page_destination(&arguments, &input.join("My Notes/Day 1.md")).unwrap();
+            let html = fs::read_to_string(&page).unwrap();
+            let from = page.strip_prefix(&arguments.output).unwrap();
+
+            for asset in ["My Notes/Photo 1.png", "My Notes/Big Notes.pdf"] {
+                let destination = asset_destination(&arguments, &input.join(asset)).unwrap();
+                assert!(destination.exists());
+
+                let url =
+                    links::relative_url(from, destination.strip_prefix(&arguments.output).unwrap());
+                assert!(html.contains(&format!("\"{}", url)), "{} in {}", url, html);
+            }
+        }
+    }
+
+    #[test]
+    fn pages_are_rebuilt_when_their_partials_change() {
+        let input = test_directory("partials");
+        fs::create_dir_all(input.join("partials")).unwrap();
+        fs::write(
+            input.join("index.md"),
+            "# Home\n\n{{> partials/contact.md}}\n",
+        )
+        .unwrap();
+        fs::write(input.join("partials/contact.md"), "Contact v1\n").unwrap();
+
+        let arguments = test_arguments(&input, &[]);
+        let report = build(&arguments).unwrap();
+        assert_eq!(
+            report.partials,
+            [input.join("partials/contact.md").canonicalize().unwrap()]
+        );
+        assert!(!arguments.output.join("partials/contact.html").exists());
+
+        // The partial must be newer than the page written from it.
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        fs::write(input.join("partials/contact.md"), "Contact v2\n").unwrap();
+        build(&arguments).unwrap();
+
+        let html = fs::read_to_string(arguments.output.join("index.html")).unwrap();
+        assert!(html.contains("Contact v2"), "{}", html);
+        assert!(!arguments.output.join("partials/contact.html").exists());
+    }
+
+    #[test]
+    fn pages_up_to_date_are_still_checked() {
+        let input = test_directory("checked");
+        fs::write(input.join("page.md"), "# Page\n\n![photo](missing.png)\n").unwrap();
+
+        let arguments = test_arguments(&input, &[]);
+        for _ in 0..2 {
+            let report = build(&arguments).unwrap();
+            assert_eq!(report.warnings.len(), 1);
+        }
+        assert_eq!(build(&arguments).unwrap().skipped, 1);
+    }
+
+    #[test]
+    fn pretty_urls_point_to_the_directory_of_the_page() {
+        let input = test_directory("pretty-urls");
+        let base_url = "https://example.org/blog";
+
+        let arguments = test_arguments(&input, &["--pretty-urls", "--output-extension", "htm"]);
+        for (path, url) in [
+            ("index.htm", "https://example.org/blog/"),
+            ("post/index.htm", "https://example.org/blog/post/"),
+            (
+                "post/index.html",
+                "https://example.org/blog/post/index.html",
+            ),
+        ] {
+            assert_eq!(page_url(&arguments, base_url, Path::new(path)), url);
+        }
+
+        let arguments = test_arguments(&input, &[]);
+        assert_eq!(
+            page_url(&arguments, base_url, Path::new("post/index.html")),
+            "https://example.org/blog/post/index.html"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::path::{Component, Path, PathBuf};
+
+use comrak::nodes::{AstNode, NodeValue};
+
+use crate::slug;
+
+// True for urls such as `https://...`, `mailto:...` or `//cdn...` which point outside the site.
+pub fn is_external(url: &str) -> bool {
+    if url.starts_with("//") {
+        return true;
+    }
+
+    match url.find(':') {
+        Some(index) => {
+            let scheme = &url[..index];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+// Only relative links are rewritten: absolute paths, external urls and anchors are kept as is.
+fn is_relative(url: &str) -> bool {
+    !url.is_empty() && !url.starts_with('/') && !url.starts_with('#') && !is_external(url)
+}
+
+// `page.md` without its extension, when it is one of the markdown `extensions`.
+fn strip_markdown_extension<'a>(path: &'a str, extensions: &[String]) -> Option<&'a str> {
+    let (stem, extension) = path.rsplit_once('.')?;
+
+    if stem.is_empty() || stem.ends_with('/') || extension.contains('/') {
+        return None;
+    }
+
+    let extension = extension.to_lowercase();
+    extensions.contains(&extension).then_some(stem)
+}
+
+// How the links of a page are rewritten, following how the pages are written.
+pub struct Rewriting<'a> {
+    // Lowercase extensions of the markdown files.
+    pub extensions: &'a [String],
+    // Extension of the generated pages, see --output-extension.
+    pub output_extension: &'a str,
+    pub pretty_urls: bool,
+    pub slugify: bool,
+    pub slug_style: slug::Style,
+    // Set for the pages written one directory deeper than their source, as with --pretty-urls,
+    // so that their relative urls still point to the same files.
+    pub moved: bool,
+    // Path the site is published under, such as `/docs`, prepended to the root-relative urls.
+    // Empty when the site is published at the root.
+    pub base_path: &'a str,
+}
+
+// The path of the `--base-url`, without its trailing slash: `https://example.com/docs/` gives
+// `/docs` and `https://example.com/` gives an empty path.
+pub fn base_path(base_url: &str) -> &str {
+    let without_scheme = match base_url.find("://") {
+        Some(index) => &base_url[index + 3..],
+        None => base_url,
+    };
+
+    match without_scheme.find('/') {
+        Some(index) => without_scheme[index..].trim_end_matches('/'),
+        None => "",
+    }
+}
+
+// `/style.css` to `/docs/style.css`. Protocol-relative urls (`//cdn...`) aren't root-relative.
+fn rewrite_root_relative_url(url: &str, base_path: &str) -> Option<String> {
+    if base_path.is_empty() || !url.starts_with('/') || url.starts_with("//") {
+        return None;
+    }
+
+    Some(format!("{}{}", base_path, url))
+}
+
+// Applies `slug::slugify_path` to the segments of a url path, `..` and `.` excepted.
+fn slugify_url_path(path: &str, style: slug::Style) -> String {
+    path.split('/')
+        .map(|segment| match segment {
+            "" | "." | ".." => segment.to_owned(),
+            _ => slug::slugify_path(Path::new(&percent_decode(segment)), style)
+                .to_string_lossy()
+                .into_owned(),
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+// Points `page.md`, `../page.md#anchor` or `page.md?query` to the generated page, `page.html`
+// with the default --output-extension.
+// With pretty urls it points to the page's directory instead: `page/`, `../page/#anchor`,
+// and `index.md` to the directory it is in.
+fn rewrite_page_url(url: &str, rewriting: &Rewriting) -> Option<String> {
+    let suffix_start = url.find(['#', '?']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(suffix_start);
+    let stem = strip_markdown_extension(path, rewriting.extensions)?;
+
+    let slugified;
+    let stem = if rewriting.slugify {
+        slugified = slugify_url_path(stem, rewriting.slug_style);
+        slugified.as_str()
+    } else {
+        stem
+    };
+
+    if !rewriting.pretty_urls {
+        return Some(match rewriting.output_extension {
+            "" => format!("{}{}", stem, suffix),
+            extension => format!("{}.{}{}", stem, extension, suffix),
+        });
+    }
+
+    match stem.strip_suffix("index") {
+        Some("") => Some(format!("./{}", suffix)),
+        Some(directory) if directory.ends_with('/') => Some(format!("{}{}", directory, suffix)),
+        _ => Some(format!("{}/{}", stem, suffix)),
+    }
+}
+
+// With --slugify the assets next to the pages are renamed like them: `Photo 1.png` points to
+// `photo-1.png`.
+fn rewrite_asset_url(url: &str, rewriting: &Rewriting) -> Option<String> {
+    if !rewriting.slugify {
+        return None;
+    }
+
+    let suffix_start = url.find(['#', '?']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(suffix_start);
+
+    Some(format!(
+        "{}{}",
+        slugify_url_path(path, rewriting.slug_style),
+        suffix
+    ))
+}
+
+// `pages` is false for the urls which can't point to a page, such as those of images.
+fn rewrite_url(url: &str, rewriting: &Rewriting, pages: bool) -> Option<String> {
+    if !is_relative(url) {
+        return rewrite_root_relative_url(url, rewriting.base_path);
+    }
+
+    let rewritten = match pages {
+        true => rewrite_page_url(url, rewriting),
+        false => None,
+    }
+    .or_else(|| rewrite_asset_url(url, rewriting));
+
+    match (rewritten, rewriting.moved) {
+        (Some(rewritten), true) => Some(format!(
+            "../{}",
+            rewritten.strip_prefix("./").unwrap_or(&rewritten)
+        )),
+        (None, true) => Some(format!("../{}", url)),
+        (rewritten, false) => rewritten,
+    }
+}
+
+// Links between pages are written against the markdown sources, which are renamed to `.html`.
+pub fn rewrite_markdown_links<'a>(ast_root: &'a AstNode<'a>, rewriting: &Rewriting) {
+    for node in ast_root.descendants() {
+        match node.data.borrow_mut().value {
+            NodeValue::Link(ref mut link) => {
+                if let Some(url) = rewrite_url(&link.url, rewriting, true) {
+                    link.url = url;
+                }
+            }
+            NodeValue::Image(ref mut image) => {
+                if let Some(url) = rewrite_url(&image.url, rewriting, false) {
+                    image.url = url;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Percent-encodes everything but the characters allowed unescaped in a url path segment.
+fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+// Decodes the `%XX` sequences of a url.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Urls of every relative link of the document, such as `page.html` or `../images/`.
+pub fn collect_relative_links<'a>(ast_root: &'a AstNode<'a>) -> Vec<String> {
+    ast_root
+        .descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::Link(ref link) if is_relative(&link.url) => Some(link.url.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// The file a relative link of the page `from` points to, both relative to the output directory.
+// A link to a directory points to its `index` page, such as `index.html`. Returns None when the
+// link leaves the output directory.
+pub fn resolve_link(from: &Path, url: &str, index: &str) -> Option<PathBuf> {
+    let end = url.find(['#', '?']).unwrap_or(url.len());
+    let path = percent_decode(&url[..end]);
+
+    let mut resolved = from.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    for component in Path::new(&path).components() {
+        match component {
+            Component::Normal(segment) => resolved.push(segment),
+            // Pops the last segment, there's none left when the link leaves the output directory.
+            Component::ParentDir if !resolved.pop() => return None,
+            _ => {}
+        }
+    }
+
+    if path.is_empty() || path.ends_with('/') {
+        resolved.push(index);
+    }
+
+    Some(resolved)
+}
+
+// Builds the url of the page `to` as seen from the page `from`, both relative to the output
+// directory: `relative_url("blog/post.html", "about.html")` is `../about.html`.
+pub fn relative_url(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from
+        .parent()
+        .map_or(Vec::new(), |parent| parent.components().collect());
+    let to: Vec<_> = to.components().collect();
+
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(left, right)| left == right)
+        .count();
+
+    let mut segments: Vec<String> = vec!["..".to_owned(); from.len() - common];
+    segments.extend(
+        to[common..]
+            .iter()
+            .map(|component| encode_segment(&component.as_os_str().to_string_lossy())),
+    );
+
+    segments.join("/")
+}
+
+// Builds the absolute url of a file from its path relative to the output directory.
+pub fn absolute_url(base_url: &str, relative_path: &Path) -> String {
+    let segments: Vec<String> = relative_path
+        .components()
+        .map(|component| encode_segment(&component.as_os_str().to_string_lossy()))
+        .collect();
+
+    format!("{}/{}", base_url.trim_end_matches('/'), segments.join("/"))
+}
+
+// Where the quoted value of the next `href=` or `src=` attribute starts, at its quote.
+fn find_url(html: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    while let Some(index) = html[offset..].find(['h', 's']) {
+        let start = offset + index;
+        let rest = &html[start..];
+        offset = start + 1;
+
+        let attribute = ["href=", "src="]
+            .iter()
+            .find(|attribute| rest.starts_with(*attribute));
+        let preceded = html[..start].ends_with(|c: char| c.is_ascii_whitespace());
+
+        if let (Some(attribute), true) = (attribute, preceded) {
+            let value = start + attribute.len();
+            if html[value..].starts_with(['"', '\'']) {
+                return Some(value);
+            }
+        }
+    }
+
+    None
+}
+
+// Replaces the value of every `href` and `src` attribute of the html by what `rewrite` returns
+// for it, values for which it returns None are kept.
+pub fn rewrite_html_urls(html: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    let mut rewritten = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = find_url(rest) {
+        let (before, after) = rest.split_at(start);
+        rewritten.push_str(before);
+
+        let quote = &after[..1];
+        let end = after[1..].find(quote).map_or(after.len(), |end| end + 1);
+        let url = &after[1..end];
+
+        rewritten.push_str(quote);
+        match rewrite(url) {
+            Some(url) => rewritten.push_str(&url),
+            None => rewritten.push_str(url),
+        }
+
+        rest = &after[end..];
+    }
+
+    rewritten.push_str(rest);
+    rewritten
+}
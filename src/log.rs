@@ -0,0 +1,206 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Messages are shown when their level is at most the current one. Warnings and errors are
+// written to stderr, everything else to stdout.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+// `-q` only keeps the errors, `-v` adds the details of every file.
+pub fn init(verbose: u64, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => Level::Error,
+        (false, 0) => Level::Info,
+        (false, _) => Level::Debug,
+    };
+
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// With --strict the warnings are shown as errors, they are still counted as warnings.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn warning_shown_as() -> (Level, &'static str) {
+    match STRICT.load(Ordering::Relaxed) {
+        true => (Level::Error, "error"),
+        false => (Level::Warn, "warn"),
+    }
+}
+
+// The warnings and the number of errors of a single build, shown or not. Each build has its own,
+// so that builds running at the same time don't take each other's: the logger only prints.
+#[derive(Default)]
+pub struct Collector {
+    warnings: Mutex<Vec<Warning>>,
+    errors: AtomicUsize,
+}
+
+impl Collector {
+    fn push(&self, warning: Warning) {
+        // A thread panicking while holding the lock leaves the list as it is.
+        match self.warnings.lock() {
+            Ok(mut warnings) => warnings.push(warning),
+            Err(poisoned) => poisoned.into_inner().push(warning),
+        }
+    }
+
+    // (warnings, errors)
+    pub fn counts(&self) -> (usize, usize) {
+        let warnings = match self.warnings.lock() {
+            Ok(warnings) => warnings.len(),
+            Err(poisoned) => poisoned.into_inner().len(),
+        };
+
+        (warnings, self.errors.load(Ordering::Relaxed))
+    }
+
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        match self.warnings.lock() {
+            Ok(mut warnings) => std::mem::take(&mut *warnings),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        }
+    }
+}
+
+thread_local! {
+    // The collector of the build running on this thread, see `collect_into`.
+    static COLLECTOR: RefCell<Option<Arc<Collector>>> = const { RefCell::new(None) };
+}
+
+// Records what is reported on this thread in `collector` until the guard is dropped. A build sets
+// it on its own thread and on the threads of its pool.
+pub fn collect_into(collector: &Arc<Collector>) -> Collecting {
+    let previous = COLLECTOR.with(|current| current.replace(Some(Arc::clone(collector))));
+    Collecting { previous }
+}
+
+pub struct Collecting {
+    previous: Option<Arc<Collector>>,
+}
+
+impl Drop for Collecting {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        COLLECTOR.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+// For the threads of a build's pool, which end with it.
+pub fn attach(collector: &Arc<Collector>) {
+    COLLECTOR.with(|current| *current.borrow_mut() = Some(Arc::clone(collector)));
+}
+
+fn with_collector(record: impl FnOnce(&Collector)) {
+    COLLECTOR.with(|current| {
+        if let Some(collector) = current.borrow().as_ref() {
+            record(collector);
+        }
+    });
+}
+
+pub fn count_error() {
+    with_collector(|collector| {
+        collector.errors.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+// What a warning is about, for the programs using the library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    // A file or a directory of the input which isn't converted.
+    Skipped,
+    // A template or a list of assets which doesn't exist.
+    MissingFile,
+    MissingImage,
+    // A heading skipping a level, with --lint-headings.
+    Heading,
+    // A frontmatter value which can't be used, such as a date.
+    Frontmatter,
+    // An asset pattern which is invalid or doesn't match anything.
+    Asset,
+    BrokenLink,
+    // The input has no markdown file.
+    NoPages,
+    Other,
+}
+
+// A warning of a build, and the file it is about when there is one.
+#[derive(Clone, Debug)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub source: Option<PathBuf>,
+}
+
+// Shows a warning and records it in the collector of the build, see `warn!`.
+pub fn warning(kind: WarningKind, source: Option<&Path>, message: String) {
+    let (level, label) = warning_shown_as();
+    if enabled(level) {
+        eprintln!("[{}] {}", label, message);
+    }
+
+    let warning = Warning {
+        kind,
+        message,
+        source: source.map(Path::to_path_buf),
+    };
+
+    with_collector(|collector| collector.push(warning));
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {{
+        $crate::log::count_error();
+        if $crate::log::enabled($crate::log::Level::Error) {
+            eprintln!("[error] {}", format_args!($($arg)*));
+        }
+    }};
+}
+
+// `warn!(kind = WarningKind::Skipped, source = path; "...", ...)` records the kind of the warning
+// and the file it is about, see `Warning`. Either may be left out.
+macro_rules! warn {
+    (kind = $kind:expr, source = $source:expr; $($arg:tt)*) => {
+        $crate::log::warning($kind, Some($source), format!($($arg)*))
+    };
+    (kind = $kind:expr; $($arg:tt)*) => {
+        $crate::log::warning($kind, None, format!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::warning($crate::log::WarningKind::Other, None, format!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Info) {
+            println!("[info] {}", format_args!($($arg)*));
+        }
+    };
+}
+
+// Per-file details, only shown with `-v`.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            println!("[debug] {}", format_args!($($arg)*));
+        }
+    };
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use fnv::FnvHasher;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Entry {
+    // Relative to the output directory.
+    pub path: PathBuf,
+    // Relative to its input directory, absolute for the assets from outside of it. The files
+    // generated from the whole site, such as the sitemap or the tag pages, have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    // Only pages have a title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub size: u64,
+    pub hash: String,
+}
+
+// FNV-1a hash of the content, as 16 hexadecimal digits. It only tells whether a file changed,
+// it isn't meant to be secure.
+pub fn hash(content: &[u8]) -> String {
+    let mut hasher = FnvHasher::default();
+    hasher.write(content);
+
+    format!("{:016x}", hasher.finish())
+}
+
+// Reads back a written file to describe it.
+pub fn entry(
+    output: &Path,
+    destination: &Path,
+    source: Option<PathBuf>,
+    title: Option<String>,
+) -> io::Result<Entry> {
+    let content = fs::read(destination)?;
+
+    Ok(Entry {
+        path: destination
+            .strip_prefix(output)
+            .unwrap_or(destination)
+            .to_path_buf(),
+        source,
+        title,
+        size: content.len() as u64,
+        hash: hash(&content),
+    })
+}
+
+// Writes every entry as a JSON array, sorted by path.
+pub fn write(destination: &Path, entries: &mut [Entry]) -> io::Result<()> {
+    entries.sort_by(|left, right| left.path.cmp(&right.path));
+
+    let manifest = serde_json::to_string_pretty(entries)?;
+
+    fs::write(destination, manifest)
+}
@@ -0,0 +1,91 @@
+// Elements whose content is copied untouched, whitespace is significant in them.
+const RAW_ELEMENTS: [&str; 5] = ["pre", "code", "textarea", "script", "style"];
+
+// Conditional comments are instructions for old browsers rather than comments.
+fn is_kept_comment(comment: &str) -> bool {
+    comment.starts_with("<!--[if") || comment.starts_with("<!--<![endif]")
+}
+
+// Length of the tag starting at the beginning of `html`, up to and including its `>`.
+// Quoted attribute values may contain a `>`.
+pub fn tag_length(html: &str) -> usize {
+    let mut quote = None;
+
+    for (index, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+
+    html.len()
+}
+
+// Name of the raw element opened by `tag`, such as `<pre class="...">`.
+fn raw_element(tag: &str) -> Option<&'static str> {
+    let name: String = tag[1..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    RAW_ELEMENTS
+        .iter()
+        .copied()
+        .find(|element| *element == name)
+}
+
+// Position of the `</element>` closing tag, case insensitive.
+fn find_closing_tag(html: &str, element: &str) -> Option<usize> {
+    let closing = format!("</{}", element);
+
+    html.to_ascii_lowercase().find(&closing)
+}
+
+// Removes comments and collapses runs of whitespace to a single space. Tags and the content of
+// `<pre>`, `<code>`, `<textarea>`, `<script>` and `<style>` are left exactly as they are.
+pub fn minify(html: &str) -> String {
+    let mut minified = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            let length = rest.find("-->").map_or(rest.len(), |end| end + 3);
+
+            if is_kept_comment(&rest[..length]) {
+                minified.push_str(&rest[..length]);
+            }
+
+            rest = &rest[length..];
+        } else if c == '<' {
+            let length = tag_length(rest);
+            let tag = &rest[..length];
+            minified.push_str(tag);
+            rest = &rest[length..];
+
+            if let Some(element) = raw_element(tag) {
+                let length = find_closing_tag(rest, element).unwrap_or(rest.len());
+                minified.push_str(&rest[..length]);
+                rest = &rest[length..];
+            }
+        } else if c.is_whitespace() {
+            let length = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            rest = &rest[length..];
+
+            // A removed comment may leave two runs of whitespace next to each other.
+            if !minified.ends_with(' ') {
+                minified.push(' ');
+            }
+        } else {
+            minified.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    minified.trim().to_owned()
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::compare_paths;
+use crate::links;
+
+// Every generated page, grouped by the folder of its source, for the `{nav}` placeholder. With
+// --pretty-urls `page.md` is written to `page/index.html`, it is still listed next to the other
+// pages of its folder rather than in a folder of its own.
+#[derive(Default)]
+pub struct Folder {
+    // (path relative to the output directory, title)
+    pages: Vec<(PathBuf, String)>,
+    folders: BTreeMap<String, Folder>,
+}
+
+impl Folder {
+    fn insert(&mut self, source: &Path, path: &Path, title: &str) {
+        let mut folder = self;
+
+        if let Some(parent) = source.parent() {
+            for component in parent.components() {
+                let name = component.as_os_str().to_string_lossy().into_owned();
+                folder = folder.folders.entry(name).or_default();
+            }
+        }
+
+        folder.pages.push((path.to_path_buf(), title.to_owned()));
+    }
+
+    // `pages` are (source relative to its input directory, path relative to the output directory,
+    // title) triples.
+    pub fn new<'a>(pages: impl Iterator<Item = (&'a Path, &'a Path, &'a str)>) -> Folder {
+        let mut root = Folder::default();
+
+        for (source, path, title) in pages {
+            root.insert(source, path, title);
+        }
+
+        root.sort();
+        root
+    }
+
+    fn sort(&mut self) {
+        self.pages
+            .sort_by(|left, right| compare_paths(&left.0, &right.0));
+
+        for folder in self.folders.values_mut() {
+            folder.sort();
+        }
+    }
+
+    // Renders the tree as nested `<ul>` lists, with links relative to `current`, whose link
+    // gets the `active` class. Pages come first, then the sub-folders.
+    pub fn render(&self, current: &Path) -> String {
+        let mut html = String::from("<ul>\n");
+
+        for (path, title) in &self.pages {
+            let class = if path == current {
+                " class=\"active\""
+            } else {
+                ""
+            };
+
+            html.push_str(&format!(
+                "<li><a{} href=\"{}\">{}</a></li>\n",
+                class,
+                links::relative_url(current, path),
+                escape(title)
+            ));
+        }
+
+        for (name, folder) in &self.folders {
+            html.push_str(&format!(
+                "<li><span>{}</span>\n{}</li>\n",
+                escape(name),
+                folder.render(current)
+            ));
+        }
+
+        html.push_str("</ul>\n");
+        html
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    // Writing to a String cannot fail.
+    let _ = comrak::html::escape(&mut escaped, text);
+    escaped
+}
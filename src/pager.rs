@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::breadcrumbs::Page;
+use crate::compare_paths;
+use crate::links;
+use crate::nav::escape;
+
+// The links to the previous and the next page of every page, by destination relative to the
+// output directory, for the `{prev_link}` and `{next_link}` placeholders.
+#[derive(Default)]
+pub struct Pager {
+    neighbours: HashMap<PathBuf, (String, String)>,
+}
+
+fn link(from: &Path, to: Option<&Page>, rel: &str) -> String {
+    match to {
+        Some(to) => format!(
+            "<a rel=\"{}\" href=\"{}\">{}</a>",
+            rel,
+            links::relative_url(from, to.destination),
+            escape(to.title)
+        ),
+        None => String::new(),
+    }
+}
+
+impl Pager {
+    // `pages` are read in sequence: those with an `order:` come first, by increasing order, then
+    // the others sorted by source, see `compare_paths`.
+    pub fn new(pages: &[(Page, Option<i64>)]) -> Pager {
+        let mut sorted: Vec<&(Page, Option<i64>)> = pages.iter().collect();
+        sorted.sort_by(|(left, left_order), (right, right_order)| {
+            (left_order.is_none(), left_order)
+                .cmp(&(right_order.is_none(), right_order))
+                .then_with(|| compare_paths(left.source, right.source))
+        });
+
+        let neighbours = sorted
+            .iter()
+            .enumerate()
+            .map(|(index, (page, _))| {
+                let previous = index.checked_sub(1).map(|index| &sorted[index].0);
+                let next = sorted.get(index + 1).map(|(page, _)| page);
+
+                let links = (
+                    link(page.destination, previous, "prev"),
+                    link(page.destination, next, "next"),
+                );
+                (page.destination.to_path_buf(), links)
+            })
+            .collect();
+
+        Pager { neighbours }
+    }
+
+    // Empty at the ends of the sequence, and for the pages which aren't part of it.
+    pub fn render(&self, destination: &Path) -> (&str, &str) {
+        self.neighbours
+            .get(destination)
+            .map_or(("", ""), |(previous, next)| (previous, next))
+    }
+}
@@ -0,0 +1,28 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const ROBOTS_FILE: &str = "robots.txt";
+
+// Writes a robots.txt (https://www.rfc-editor.org/rfc/rfc9309) letting every crawler in but on the
+// `disallowed` paths, and pointing them to the sitemap when there is one.
+pub fn write(destination: &Path, disallowed: &[String], sitemap: Option<&str>) -> io::Result<()> {
+    let mut robots = String::from("User-agent: *\n");
+
+    if disallowed.is_empty() {
+        robots.push_str("Disallow:\n");
+    }
+
+    for path in disallowed {
+        match path.starts_with('/') {
+            true => robots.push_str(&format!("Disallow: {}\n", path)),
+            false => robots.push_str(&format!("Disallow: /{}\n", path)),
+        }
+    }
+
+    if let Some(sitemap) = sitemap {
+        robots.push_str(&format!("\nSitemap: {}\n", sitemap));
+    }
+
+    fs::write(destination, robots)
+}
@@ -0,0 +1,223 @@
+use crate::minify::tag_length;
+
+// Elements of the documentation pages, with the attributes they keep besides `GLOBAL_ATTRIBUTES`.
+// Any other element is removed, its content kept.
+const ALLOWED: &[(&str, &[&str])] = &[
+    ("a", &["href", "rel"]),
+    ("abbr", &[]),
+    ("b", &[]),
+    ("blockquote", &[]),
+    ("br", &[]),
+    ("code", &[]),
+    ("dd", &[]),
+    ("del", &[]),
+    ("details", &["open"]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("figcaption", &[]),
+    ("figure", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "width", "height", "loading"]),
+    ("input", &["type", "checked", "disabled"]),
+    ("ins", &[]),
+    ("kbd", &[]),
+    ("li", &[]),
+    ("mark", &[]),
+    ("ol", &["start"]),
+    ("p", &[]),
+    ("pre", &[]),
+    ("q", &[]),
+    ("s", &[]),
+    ("samp", &[]),
+    ("section", &[]),
+    // The inline styles of the syntax highlighting are removed with any other.
+    ("span", &[]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["align"]),
+    ("tfoot", &[]),
+    ("th", &["align"]),
+    ("thead", &[]),
+    ("tr", &[]),
+    ("ul", &[]),
+];
+
+const GLOBAL_ATTRIBUTES: &[&str] = &["class", "id", "title", "lang", "dir"];
+
+// Removed along with their content.
+const DROPPED_ELEMENTS: &[&str] = &["script", "style", "iframe", "object", "embed", "template"];
+
+// Schemes allowed in `href` and `src`, relative urls are always allowed.
+const SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+fn is_allowed_attribute(element: &[&str], name: &str) -> bool {
+    GLOBAL_ATTRIBUTES.contains(&name)
+        || element.contains(&name)
+        || name.starts_with("aria-")
+        || name.starts_with("data-")
+}
+
+// The browsers decode the entities of the attributes and ignore the spaces around the url, so
+// `java&#115;cript:` is a scheme as well.
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim_start();
+    let path_start = url.find(['/', '?', '#']).unwrap_or(url.len());
+
+    match url[..path_start].find(|c: char| c == ':' || c == '&' || c.is_control()) {
+        Some(end) if url[end..].starts_with(':') => {
+            SCHEMES.contains(&url[..end].to_ascii_lowercase().as_str())
+        }
+        Some(_) => false,
+        None => true,
+    }
+}
+
+// The (name, value) attributes of the inside of a tag, after its name. Names are lowercase.
+fn parse_attributes(mut rest: &str) -> Vec<(String, Option<String>)> {
+    let mut attributes = Vec::new();
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return attributes;
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote) if quote == '"' || quote == '\'' => {
+                        let end = after[1..].find(quote).map_or(after.len(), |end| end + 1);
+                        (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remaining;
+                Some(value.to_owned())
+            }
+            None => None,
+        };
+
+        attributes.push((name, value));
+    }
+}
+
+// The tag with the allowed attributes only, None when the element isn't allowed.
+fn sanitize_tag(tag: &str) -> Option<String> {
+    let inside = tag.strip_prefix('<')?.strip_suffix('>')?;
+    let (closing, inside) = match inside.strip_prefix('/') {
+        Some(inside) => (true, inside),
+        None => (false, inside),
+    };
+
+    let name_end = inside
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inside.len());
+    let name = inside[..name_end].to_ascii_lowercase();
+    let (_, allowed) = ALLOWED.iter().find(|(element, _)| *element == name)?;
+
+    if closing {
+        return Some(format!("</{}>", name));
+    }
+
+    let mut sanitized = format!("<{}", name);
+    for (attribute, value) in parse_attributes(&inside[name_end..]) {
+        if !is_allowed_attribute(allowed, &attribute) {
+            continue;
+        }
+
+        match value {
+            Some(value) if (attribute == "href" || attribute == "src") && !is_safe_url(&value) => {}
+            Some(value) => sanitized.push_str(&format!(
+                " {}=\"{}\"",
+                attribute,
+                value.replace('"', "&quot;")
+            )),
+            None => sanitized.push_str(&format!(" {}", attribute)),
+        }
+    }
+
+    if inside.trim_end().ends_with('/') {
+        sanitized.push_str(" /");
+    }
+    sanitized.push('>');
+
+    Some(sanitized)
+}
+
+// Keeps the elements and attributes of `ALLOWED` only, for --sanitize. The `href` and `src` with
+// a scheme other than http, https or mailto are removed, and so are the comments.
+pub fn sanitize(html: &str) -> String {
+    let mut sanitized = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        sanitized.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+
+        let is_tag = rest[1..]
+            .trim_start_matches('/')
+            .starts_with(|c: char| c.is_ascii_alphabetic());
+        if !is_tag && !rest.starts_with("<!") && !rest.starts_with("<?") {
+            sanitized.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        }
+
+        let length = tag_length(rest);
+        let tag = &rest[..length];
+        rest = &rest[length..];
+
+        let name: String = tag[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if DROPPED_ELEMENTS.contains(&name.as_str()) {
+            if name == "embed" || tag.ends_with("/>") {
+                continue;
+            }
+
+            let closing = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(end) => &rest[end + tag_length(&rest[end..])..],
+                None => "",
+            };
+            continue;
+        }
+
+        if let Some(tag) = sanitize_tag(tag) {
+            sanitized.push_str(&tag);
+        }
+    }
+
+    sanitized.push_str(rest);
+    sanitized
+}
@@ -0,0 +1,22 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+#[derive(Serialize)]
+pub struct Entry {
+    pub url: String,
+    pub title: String,
+    // Plain text of the page, without any markup.
+    pub text: String,
+}
+
+// Writes every entry as a JSON array, in the usual format of client-side search libraries.
+pub fn write(destination: &Path, entries: &[Entry]) -> io::Result<()> {
+    let index = serde_json::to_string(entries)?;
+
+    fs::write(destination, index)
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use crate::links;
+
+pub const DEFAULT_PORT: u16 = 8000;
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("pdf") => "application/pdf",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+// Maps the request path to a file of the served directory. Paths trying to escape it with `..`
+// are refused.
+fn resolve(root: &Path, request_path: &str) -> Option<PathBuf> {
+    let path = links::percent_decode(request_path.split(['?', '#']).next().unwrap_or(""));
+    let relative = Path::new(path.trim_start_matches('/'));
+
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    Some(root.join(relative))
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\n", status)?;
+
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+fn not_found(stream: &mut TcpStream) -> io::Result<()> {
+    respond(
+        stream,
+        "404 Not Found",
+        &[("Content-Type", "text/plain; charset=utf-8")],
+        b"404 Not Found",
+    )
+}
+
+// The url of a directory with its trailing slash, before the query: `/blog?x=1` is `/blog/?x=1`.
+fn directory_location(target: &str) -> String {
+    match target.split_once('?') {
+        Some((path, query)) => format!("{}/?{}", path, query),
+        None => format!("{}/", target),
+    }
+}
+
+// `index` is the file served for a directory, such as `index.html`.
+fn handle(root: &Path, index: &str, mut stream: TcpStream) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => return respond(&mut stream, "400 Bad Request", &[], b""),
+    };
+
+    if method != "GET" && method != "HEAD" {
+        return respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            &[("Allow", "GET, HEAD")],
+            b"",
+        );
+    }
+
+    let mut path = match resolve(root, target) {
+        Some(path) => path,
+        None => return not_found(&mut stream),
+    };
+
+    if path.is_dir() {
+        // Relative links of the index page only work from the directory's url.
+        if !target.split('?').next().unwrap_or("").ends_with('/') {
+            let location = directory_location(target);
+            return respond(
+                &mut stream,
+                "301 Moved Permanently",
+                &[("Location", &location)],
+                b"",
+            );
+        }
+
+        path.push(index);
+    }
+
+    match fs::read(&path) {
+        Ok(content) => {
+            let body: &[u8] = if method == "HEAD" { &[] } else { &content };
+            respond(
+                &mut stream,
+                "200 OK",
+                &[("Content-Type", content_type(&path))],
+                body,
+            )
+        }
+        Err(_) => not_found(&mut stream),
+    }
+}
+
+// Listening is done first, separately, so that an unavailable port is reported right away.
+pub fn bind(port: u16) -> io::Result<TcpListener> {
+    TcpListener::bind(("127.0.0.1", port))
+}
+
+// The command opening a url in the default browser.
+fn browser_command(url: &str) -> Command {
+    let mut command;
+    if cfg!(target_os = "windows") {
+        command = Command::new("cmd");
+        command.args(["/C", "start", "", url]);
+    } else if cfg!(target_os = "macos") {
+        command = Command::new("open");
+        command.arg(url);
+    } else {
+        command = Command::new("xdg-open");
+        command.arg(url);
+    }
+
+    command
+}
+
+// Opens the index page of the server in the default browser, for --open. It is done from its own
+// thread since some browsers keep the command running. Without a browser this is only a warning.
+pub fn open_browser(port: u16) {
+    let url = format!("http://localhost:{}/", port);
+
+    thread::spawn(move || match browser_command(&url).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!(
+            "Could not open a browser at '{}', it exited with {}.",
+            url, status
+        ),
+        Err(error) => warn!("Could not open a browser at '{}'. Error: {}", url, error),
+    });
+}
+
+// Serves the files of `root` on localhost, each connection in its own thread, with `index` for
+// the directories. This never returns.
+pub fn serve(listener: TcpListener, root: &Path, index: &str) {
+    if let Ok(address) = listener.local_addr() {
+        info!(
+            "Serving '{}' at http://localhost:{}/",
+            root.display(),
+            address.port()
+        );
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let root = root.to_path_buf();
+                let index = index.to_owned();
+
+                thread::spawn(move || {
+                    if let Err(error) = handle(&root, &index, stream) {
+                        debug!("Could not answer a request. Error: {}", error);
+                    }
+                });
+            }
+            Err(error) => warn!("Invalid connection. Error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::links;
+use crate::nav::escape;
+use crate::slug;
+
+// A page of the site, with its destination relative to the output directory and its html.
+pub struct Section<'a> {
+    pub destination: &'a Path,
+    pub title: &'a str,
+    pub html: &'a str,
+}
+
+// `guide/intro.html` gives `page-guide-intro`, numbered when another page has the same id.
+fn section_ids(sections: &[Section], style: slug::Style) -> HashMap<PathBuf, String> {
+    let mut used = HashSet::new();
+
+    sections
+        .iter()
+        .map(|section| {
+            let stem = section.destination.with_extension("");
+            let slug = format!("page-{}", slug::slugify(&stem.to_string_lossy(), style));
+
+            let mut id = slug.clone();
+            let mut suffix = 0;
+            while used.contains(&id) {
+                suffix += 1;
+                id = format!("{}{}{}", slug, style.separator, suffix);
+            }
+            used.insert(id.clone());
+
+            (section.destination.to_path_buf(), id)
+        })
+        .collect()
+}
+
+// The pages of the site in one document, written at `file` relative to the output directory.
+// A list of the pages comes first, linking to their `<section>`. Links from one page to another
+// point to its section and the other relative urls are rewritten to be relative to `file`. The
+// headings keep their ids, a link to a heading of another page points to it. `index` is the name
+// of the pages a link to a directory points to.
+pub fn combine(sections: &[Section], file: &Path, index: &str, style: slug::Style) -> String {
+    let ids = section_ids(sections, style);
+
+    let mut html = String::from("<nav class=\"contents\">\n<ol>\n");
+    for section in sections {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a></li>\n",
+            ids[section.destination],
+            escape(section.title)
+        ));
+    }
+    html.push_str("</ol>\n</nav>\n");
+
+    for section in sections {
+        let content = links::rewrite_html_urls(section.html, |url| {
+            if url.is_empty() || url.starts_with(['#', '/']) || links::is_external(url) {
+                return None;
+            }
+
+            let target = links::resolve_link(section.destination, url, index)?;
+            let suffix = url.find(['#', '?']).map_or("", |start| &url[start..]);
+
+            Some(match (ids.get(&target), url.find('#')) {
+                (Some(_), Some(anchor)) => url[anchor..].to_owned(),
+                (Some(id), None) => format!("#{}", id),
+                (None, _) => format!("{}{}", links::relative_url(file, &target), suffix),
+            })
+        });
+
+        html.push_str("<hr class=\"page-separator\">\n");
+        html.push_str(&format!(
+            "<section class=\"page\" id=\"{}\">\n{}</section>\n",
+            ids[section.destination], content
+        ));
+    }
+
+    html
+}
@@ -0,0 +1,50 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+pub struct Entry {
+    pub url: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Writes a sitemap (https://www.sitemaps.org/protocol.html) listing every entry, sorted by url.
+pub fn write(destination: &Path, entries: &mut [Entry]) -> io::Result<()> {
+    entries.sort_by(|left, right| left.url.cmp(&right.url));
+
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for entry in entries.iter() {
+        sitemap.push_str("  <url>\n");
+        sitemap.push_str(&format!("    <loc>{}</loc>\n", escape_xml(&entry.url)));
+
+        if let Some(last_modified) = entry.last_modified {
+            let date: DateTime<Utc> = last_modified.into();
+            sitemap.push_str(&format!(
+                "    <lastmod>{}</lastmod>\n",
+                date.format("%Y-%m-%d")
+            ));
+        }
+
+        sitemap.push_str("  </url>\n");
+    }
+
+    sitemap.push_str("</urlset>\n");
+
+    fs::write(destination, sitemap)
+}
@@ -0,0 +1,135 @@
+use std::path::{Component, Path, PathBuf};
+
+// How the slugs are written, from --slug-separator and --slug-lowercase. The file names and the
+// heading ids use the same one, so that the links and the anchors agree.
+#[derive(Clone, Copy)]
+pub struct Style {
+    pub separator: char,
+    pub lowercase: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            separator: '-',
+            lowercase: true,
+        }
+    }
+}
+
+// Lowercases the text unless the style keeps the case, keeps alphanumeric characters (any script,
+// not only ASCII) and turns every
+// run of other characters into a single separator, without leading or trailing ones:
+// `What's new in 2.0?` becomes `what-s-new-in-2-0`. The slug is empty when the text has no
+// such character.
+pub fn slugify(text: &str, style: Style) -> String {
+    let mut slug = String::new();
+
+    for character in text.chars() {
+        if character.is_alphanumeric() && style.lowercase {
+            slug.extend(character.to_lowercase());
+        } else if character.is_alphanumeric() {
+            slug.push(character);
+        } else if !slug.is_empty() && !slug.ends_with(style.separator) {
+            slug.push(style.separator);
+        }
+    }
+
+    while slug.ends_with(style.separator) {
+        slug.pop();
+    }
+
+    slug
+}
+
+// A name whose slug would be empty is kept as it is.
+fn slugify_name(name: &str, style: Style) -> String {
+    match slugify(name, style) {
+        slug if slug.is_empty() => name.to_owned(),
+        slug => slug,
+    }
+}
+
+// Slugifies every directory of a relative path and the file name, but not its extension:
+// `My Notes/First Day.md` becomes `my-notes/first-day.md`.
+pub fn slugify_path(path: &Path, style: Style) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    let mut slugified = PathBuf::new();
+
+    for (index, component) in components.iter().enumerate() {
+        let name = match component {
+            Component::Normal(name) => name.to_string_lossy(),
+            other => {
+                slugified.push(other.as_os_str());
+                continue;
+            }
+        };
+
+        if index + 1 < components.len() {
+            slugified.push(slugify_name(&name, style));
+            continue;
+        }
+
+        match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => {
+                slugified.push(format!("{}.{}", slugify_name(stem, style), extension))
+            }
+            _ => slugified.push(slugify_name(&name, style)),
+        }
+    }
+
+    slugified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNDERSCORE: Style = Style {
+        separator: '_',
+        lowercase: true,
+    };
+
+    const KEEP_CASE: Style = Style {
+        separator: '-',
+        lowercase: false,
+    };
+
+    #[test]
+    fn keeps_accented_letters() {
+        assert_eq!(slugify("Café Déjà Vu", Style::default()), "café-déjà-vu");
+        assert_eq!(slugify("Ÿ ÉTÉ", Style::default()), "ÿ-été");
+        assert_eq!(slugify("Ÿ ÉTÉ", KEEP_CASE), "Ÿ-ÉTÉ");
+    }
+
+    #[test]
+    fn collapses_consecutive_separators() {
+        assert_eq!(
+            slugify("What's  new -- in 2.0?", Style::default()),
+            "what-s-new-in-2-0"
+        );
+        assert_eq!(slugify("a - _ b", UNDERSCORE), "a_b");
+    }
+
+    #[test]
+    fn trims_leading_and_trailing_separators() {
+        assert_eq!(
+            slugify("  -- Hello, world! --  ", Style::default()),
+            "hello-world"
+        );
+        assert_eq!(slugify("__init__", UNDERSCORE), "init");
+        assert_eq!(slugify("?!", Style::default()), "");
+    }
+
+    #[test]
+    fn slugifies_every_name_of_a_path_but_not_the_extension() {
+        assert_eq!(
+            slugify_path(Path::new("My Notes/Day 1.MD"), Style::default()),
+            Path::new("my-notes/day-1.MD")
+        );
+        assert_eq!(
+            slugify_path(Path::new("../?!/Read Me.txt"), Style::default()),
+            Path::new("../?!/read-me.txt")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::links;
+use crate::nav::escape;
+use crate::slug;
+
+pub const TAGS_DIRECTORY: &str = "tags";
+
+// A tagged page, its path relative to the output directory.
+#[derive(Clone, Copy)]
+pub struct Page<'a> {
+    pub path: &'a Path,
+    pub title: &'a str,
+    pub excerpt: &'a str,
+}
+
+// The frontmatter `tags: [rust, cli]` is flattened to `rust, cli`.
+pub fn parse(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+// Name of the tags index page, which no tag page can take.
+const INDEX_NAME: &str = "index";
+
+// Where a tag page is written, as a page of the input would be: `tags/<name>.html`, `html` being
+// the --output-extension, or with --pretty-urls `tags/<name>/index.html`. The index of the tags
+// stays `tags/index.html`.
+fn page_path(name: &str, output_extension: &str, pretty_urls: bool) -> PathBuf {
+    let file_name = |name: &str| match output_extension {
+        "" => name.to_owned(),
+        extension => format!("{}.{}", name, extension),
+    };
+
+    match pretty_urls && name != INDEX_NAME {
+        true => Path::new(TAGS_DIRECTORY)
+            .join(name)
+            .join(file_name(INDEX_NAME)),
+        false => Path::new(TAGS_DIRECTORY).join(file_name(name)),
+    }
+}
+
+// `tags/<tag>.html` for every tag, relative to the output directory. Tags whose slugs collide,
+// such as `c` and `c++` or a tag `index`, are told apart by a number: `c.html` and `c-2.html`,
+// the first tag by name keeping the plain slug.
+pub fn tag_paths<'a>(
+    tags: impl Iterator<Item = &'a String>,
+    style: slug::Style,
+    output_extension: &str,
+    pretty_urls: bool,
+) -> BTreeMap<String, PathBuf> {
+    let mut taken: HashSet<String> = HashSet::new();
+    taken.insert(INDEX_NAME.to_owned());
+
+    let mut paths = BTreeMap::new();
+    for tag in tags {
+        let slug = match slug::slugify(tag, style) {
+            slug if slug.is_empty() => tag.to_owned(),
+            slug => slug,
+        };
+
+        let mut name = slug.clone();
+        let mut number = 2;
+        while !taken.insert(name.clone()) {
+            name = format!("{}{}{}", slug, style.separator, number);
+            number += 1;
+        }
+
+        paths.insert(
+            tag.to_owned(),
+            page_path(&name, output_extension, pretty_urls),
+        );
+    }
+
+    paths
+}
+
+pub fn index_path(output_extension: &str) -> PathBuf {
+    page_path(INDEX_NAME, output_extension, false)
+}
+
+// Groups the pages by tag, both sorted by name and by path.
+pub fn group<'a>(
+    pages: impl Iterator<Item = (Page<'a>, Vec<String>)>,
+) -> BTreeMap<String, Vec<Page<'a>>> {
+    let mut tagged: BTreeMap<String, Vec<Page>> = BTreeMap::new();
+
+    for (page, tags) in pages {
+        for tag in tags {
+            tagged.entry(tag).or_default().push(page);
+        }
+    }
+
+    for pages in tagged.values_mut() {
+        pages.sort_by(|left, right| left.path.cmp(right.path));
+    }
+
+    tagged
+}
+
+// Lists the pages of a tag: their title, link and excerpt. `current` is the tag's page.
+pub fn render_tag(tag: &str, pages: &[Page], current: &Path) -> String {
+    let mut html = format!("<h1>{}</h1>\n<ul class=\"tag-pages\">\n", escape(tag));
+
+    for page in pages {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>\n<p>{}</p></li>\n",
+            links::relative_url(current, page.path),
+            escape(page.title),
+            escape(page.excerpt)
+        ));
+    }
+
+    html.push_str("</ul>\n");
+    html
+}
+
+// Lists every tag with its number of pages, `paths` being those of `tag_paths`. `current` is the
+// index page.
+pub fn render_index(
+    tagged: &BTreeMap<String, Vec<Page>>,
+    paths: &BTreeMap<String, PathBuf>,
+    current: &Path,
+) -> String {
+    let mut html = String::from("<h1>Tags</h1>\n<ul class=\"tags\">\n");
+
+    for (tag, pages) in tagged {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> ({})</li>\n",
+            links::relative_url(current, &paths[tag]),
+            escape(tag),
+            pages.len()
+        ));
+    }
+
+    html.push_str("</ul>\n");
+    html
+}
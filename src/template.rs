@@ -0,0 +1,182 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+// How placeholders are written, given as a sample such as `{name}` or `{{name}}`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Syntax {
+    open: String,
+    close: String,
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(sample: &str) -> Result<Syntax, String> {
+        match sample.split_once("name") {
+            Some((open, close)) if !open.is_empty() && !close.is_empty() => Ok(Syntax {
+                open: open.to_owned(),
+                close: close.to_owned(),
+            }),
+            _ => Err(format!(
+                "Invalid placeholder syntax '{}', expected the word `name` between delimiters such as `{{{{name}}}}`.",
+                sample
+            )),
+        }
+    }
+}
+
+impl Syntax {
+    // The placeholder `name` written in this syntax.
+    pub fn placeholder(&self, name: &str) -> String {
+        format!("{}{}{}", self.open, name, self.close)
+    }
+}
+
+impl TryFrom<String> for Syntax {
+    type Error = String;
+
+    fn try_from(sample: String) -> Result<Syntax, String> {
+        sample.parse()
+    }
+}
+
+// A template split once around its placeholders, so that a page is assembled by putting the
+// pieces together instead of searching the whole template for every placeholder.
+#[derive(Default)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    Literal(String),
+    // The name of the placeholder.
+    Placeholder(String),
+    // `{#if key}...{/if}`, the segments are only written when the page's metadata has the key.
+    Conditional(String, Vec<Segment>),
+}
+
+// `{#if key}` gives `key`.
+fn condition(tag: &str) -> Option<&str> {
+    let key = tag.strip_prefix("#if ")?.trim();
+    (!key.is_empty() && !key.contains(char::is_whitespace)).then_some(key)
+}
+
+fn push_literal(segments: &mut Vec<Segment>, literal: &mut String) {
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(std::mem::take(literal)));
+    }
+}
+
+// Conditionals are only recognized when `conditionals` is set, they don't nest.
+fn parse_segments(text: &str, syntax: &Syntax, known: &[&str], conditionals: bool) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(&syntax.open) {
+        let after = &rest[start + syntax.open.len()..];
+        let tag = after.find(&syntax.close).map(|end| &after[..end]);
+
+        if let Some(name) = tag.filter(|name| known.contains(name)) {
+            literal.push_str(&rest[..start]);
+            push_literal(&mut segments, &mut literal);
+
+            segments.push(Segment::Placeholder(name.to_owned()));
+            rest = &after[name.len() + syntax.close.len()..];
+            continue;
+        }
+
+        // An `{#if key}` without its `{/if}` is kept as it is written.
+        let end_tag = format!("{}/if{}", syntax.open, syntax.close);
+        let conditional = tag
+            .filter(|_| conditionals)
+            .and_then(|tag| Some((tag, condition(tag)?)))
+            .and_then(|(tag, key)| {
+                let body = &after[tag.len() + syntax.close.len()..];
+                let end = body.find(&end_tag)?;
+                Some((key, &body[..end], &body[end + end_tag.len()..]))
+            });
+
+        match conditional {
+            Some((key, body, remaining)) => {
+                literal.push_str(&rest[..start]);
+                push_literal(&mut segments, &mut literal);
+
+                let body = parse_segments(body, syntax, known, false);
+                segments.push(Segment::Conditional(key.to_owned(), body));
+                rest = remaining;
+            }
+            None => {
+                literal.push_str(&rest[..start + syntax.open.len()]);
+                rest = after;
+            }
+        }
+    }
+
+    literal.push_str(rest);
+    push_literal(&mut segments, &mut literal);
+
+    segments
+}
+
+fn contains(segments: &[Segment], name: &str) -> bool {
+    segments.iter().any(|segment| match segment {
+        Segment::Placeholder(placeholder) => placeholder == name,
+        Segment::Conditional(_, segments) => contains(segments, name),
+        Segment::Literal(_) => false,
+    })
+}
+
+fn push_pieces<'a>(
+    segments: &'a [Segment],
+    values: &[(&str, &'a str)],
+    is_set: &dyn Fn(&str) -> bool,
+    pieces: &mut Vec<&'a str>,
+) {
+    for segment in segments {
+        match segment {
+            Segment::Literal(text) => pieces.push(text),
+            Segment::Placeholder(name) => pieces.push(
+                values
+                    .iter()
+                    .find(|(placeholder, _)| placeholder == name)
+                    .map_or("", |(_, value)| value),
+            ),
+            Segment::Conditional(key, segments) if is_set(key) => {
+                push_pieces(segments, values, is_set, pieces)
+            }
+            Segment::Conditional(..) => {}
+        }
+    }
+}
+
+impl Template {
+    // Only the `known` placeholder names are filled, anything else is kept as it is written so
+    // that a CSS rule or a script doesn't get replaced by accident. So are the constructs other
+    // than `{#if key}...{/if}`, which doesn't nest.
+    pub fn parse(text: &str, syntax: &Syntax, known: &[&str]) -> Template {
+        Template {
+            segments: parse_segments(text, syntax, known, true),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        contains(&self.segments, name)
+    }
+
+    // The pieces of the filled template, in order. `values` are (name, value) pairs, a
+    // placeholder without a value is left empty. `is_set` tells whether the page's metadata has
+    // the key of a conditional.
+    pub fn pieces<'a>(
+        &'a self,
+        values: &[(&str, &'a str)],
+        is_set: &dyn Fn(&str) -> bool,
+    ) -> Vec<&'a str> {
+        let mut pieces = Vec::new();
+        push_pieces(&self.segments, values, is_set, &mut pieces);
+        pieces
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+
+use comrak::adapters::{HeadingAdapter, HeadingMeta};
+use comrak::nodes::{AstNode, NodeValue, Sourcepos};
+
+use crate::nav::escape;
+use crate::slug;
+
+pub struct Heading {
+    pub level: u8,
+    pub id: String,
+    pub text: String,
+}
+
+// A heading without any letter or digit still needs an id.
+fn slugify(text: &str, style: slug::Style) -> String {
+    match slug::slugify(text, style) {
+        slug if slug.is_empty() => "section".to_owned(),
+        slug => slug,
+    }
+}
+
+// Lists every heading of the document in order, giving each one a unique id: the prefix followed
+// by the slug of its text. Repeated ids get the first free numeric suffix: `intro`, `intro-1`,
+// `intro-2`... with the separator of the style.
+pub fn collect_headings<'a>(
+    ast_root: &'a AstNode<'a>,
+    prefix: &str,
+    style: slug::Style,
+) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut used = HashSet::<String>::new();
+
+    for node in ast_root.descendants() {
+        let level = match node.data.borrow().value {
+            NodeValue::Heading(ref heading) => heading.level,
+            _ => continue,
+        };
+
+        let text = node.collect_text();
+        let slug = format!("{}{}", prefix, slugify(&text, style));
+
+        let mut id = slug.clone();
+        let mut suffix = 0;
+        while used.contains(&id) {
+            suffix += 1;
+            id = format!("{}{}{}", slug, style.separator, suffix);
+        }
+        used.insert(id.clone());
+
+        headings.push(Heading { level, id, text });
+    }
+
+    headings
+}
+
+// The headings more than one level below the heading before them, as an `h3` right after an
+// `h1`, with the level of that previous heading.
+pub fn skipped_levels(headings: &[Heading]) -> Vec<(&Heading, u8)> {
+    headings
+        .windows(2)
+        .filter(|pair| pair[1].level > pair[0].level + 1)
+        .map(|pair| (&pair[1], pair[0].level))
+        .collect()
+}
+
+// The position of `heading` among the headings listed by `collect_headings`.
+pub fn heading_index<'a>(ast_root: &'a AstNode<'a>, heading: &'a AstNode<'a>) -> Option<usize> {
+    ast_root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Heading(_)))
+        .position(|node| node.same_node(heading))
+}
+
+// Renders the headings as nested `<ul>` lists linking to their anchors.
+// The heading at `title`, the page title, is skipped, see --title-level.
+pub fn render(headings: &[Heading], title: Option<usize>) -> String {
+    let mut html = String::new();
+    let mut levels: Vec<u8> = Vec::new();
+
+    for (_, heading) in headings
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| Some(*index) != title)
+    {
+        while let Some(&level) = levels.last() {
+            if heading.level >= level {
+                break;
+            }
+
+            html.push_str("</li>\n</ul>\n");
+            levels.pop();
+        }
+
+        match levels.last() {
+            Some(&level) if level == heading.level => html.push_str("</li>\n"),
+            _ => {
+                html.push_str("<ul>\n");
+                levels.push(heading.level);
+            }
+        }
+
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            heading.id,
+            escape(&heading.text)
+        ));
+    }
+
+    for _ in levels {
+        html.push_str("</li>\n</ul>\n");
+    }
+
+    html
+}
+
+// Renders the headings with the ids computed by `collect_headings`, optionally preceded by a
+// `#` link to themselves. comrak renders the headings in document order, so ids are handed out
+// in that same order.
+pub struct HeadingIds {
+    ids: Mutex<std::vec::IntoIter<String>>,
+    anchors: bool,
+}
+
+impl HeadingIds {
+    pub fn new(headings: &[Heading], anchors: bool) -> HeadingIds {
+        let ids: Vec<String> = headings.iter().map(|heading| heading.id.clone()).collect();
+
+        HeadingIds {
+            ids: Mutex::new(ids.into_iter()),
+            anchors,
+        }
+    }
+}
+
+impl HeadingAdapter for HeadingIds {
+    fn enter(
+        &self,
+        output: &mut dyn fmt::Write,
+        heading: &HeadingMeta,
+        _sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        match self.ids.lock().unwrap().next() {
+            Some(id) if self.anchors => write!(
+                output,
+                "<h{} id=\"{}\"><a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>",
+                heading.level, id, id
+            ),
+            Some(id) => write!(output, "<h{} id=\"{}\">", heading.level, id),
+            None => write!(output, "<h{}>", heading.level),
+        }
+    }
+
+    fn exit(&self, output: &mut dyn fmt::Write, heading: &HeadingMeta) -> fmt::Result {
+        writeln!(output, "</h{}>", heading.level)
+    }
+}
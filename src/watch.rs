@@ -0,0 +1,257 @@
+use std::collections::{BTreeSet, HashSet};
+use std::error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::exclude::Exclusions;
+use crate::{
+    build, create_renderer, is_adjacent_asset, is_markdown_file, is_too_large, process_file,
+    read_templates, BuildReport, Opt, FOOTER_FILE, HEADER_FILE,
+};
+
+// Editors frequently write a file several times for a single save, events arriving
+// within this window are coalesced into one rebuild.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+enum Rebuild {
+    Nothing,
+    Pages(Vec<PathBuf>),
+    Site,
+}
+
+fn collect_event(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) => {
+            if !matches!(event.kind, EventKind::Access(_)) {
+                changed.extend(event.paths);
+            }
+        }
+        Err(error) => warn!("Error while watching files: {}", error),
+    }
+}
+
+// A template or the assets list affects every page, in which case the whole site is rebuilt.
+// Otherwise only the markdown files that changed (and still exist) are converted again.
+fn classify(
+    arguments: &Opt,
+    exclusions: &Exclusions,
+    changed: &BTreeSet<PathBuf>,
+    site_files: &[PathBuf],
+    layouts: &Path,
+) -> Rebuild {
+    let mut pages = Vec::new();
+
+    for path in changed {
+        // Writing the output would otherwise trigger a rebuild loop when it sits inside the input.
+        if path.starts_with(&arguments.output) {
+            continue;
+        }
+
+        if site_files.iter().any(|file| file == path) || path.starts_with(layouts) {
+            return Rebuild::Site;
+        }
+
+        if exclusions.is_excluded(path, path.is_dir()) {
+            continue;
+        }
+
+        // The static files and the templates of the input's directories are handled by a full
+        // build.
+        let template_override = path
+            .file_name()
+            .is_some_and(|name| name == HEADER_FILE || name == FOOTER_FILE);
+        if path.starts_with(&arguments.static_dir) || template_override {
+            return Rebuild::Site;
+        }
+
+        let is_page = is_markdown_file(&arguments.extensions, path);
+
+        if is_page && path.is_file() {
+            if !is_too_large(arguments.max_file_size, path) {
+                pages.push(path.to_path_buf());
+            }
+        } else if !is_page && arguments.copy_adjacent && is_adjacent_asset(arguments, path) {
+            // Assets are only copied by a full build.
+            return Rebuild::Site;
+        }
+    }
+
+    if pages.is_empty() {
+        Rebuild::Nothing
+    } else {
+        Rebuild::Pages(pages)
+    }
+}
+
+// Quoted for the logs. Paths which aren't valid UTF-8 are shown with replacement characters.
+fn describe(path: &Path) -> String {
+    format!("'{}'", path.display())
+}
+
+// What the last full build found out about the pages.
+struct Site {
+    // Whether some pages are written at their permalink.
+    permalinks: bool,
+    // The markdown files included by the pages.
+    partials: HashSet<PathBuf>,
+}
+
+impl Site {
+    fn new(report: &BuildReport) -> Site {
+        Site {
+            permalinks: report.permalinks > 0,
+            partials: report.partials.iter().cloned().collect(),
+        }
+    }
+
+    // A partial is rebuilt with the pages including it, by a full build.
+    fn is_partial(&self, file: &Path) -> bool {
+        file.canonicalize()
+            .is_ok_and(|canonical| self.partials.contains(&canonical))
+    }
+
+    // Whether a page rebuilt on its own includes a file which isn't known to be a partial yet,
+    // which would still be published as a page.
+    fn has_new_partials(&self, includes: &[PathBuf]) -> bool {
+        includes
+            .iter()
+            .any(|include| !self.partials.contains(include))
+    }
+}
+
+// Builds the whole site, keeping track of what it found out.
+fn build_site(arguments: &Opt, site: &mut Site) {
+    match build(arguments) {
+        Ok(report) => *site = Site::new(&report),
+        Err(error) => {
+            eprintln!("{}", error);
+            *site = Site::new(&error.report);
+        }
+    }
+}
+
+// Watches the input directories (and the templates) and rebuilds whatever is affected by a change.
+// This never returns unless the watcher stops or fails. `report` is that of the build before.
+pub fn watch(arguments: &Opt, report: &BuildReport) -> Result<(), Box<dyn error::Error + 'static>> {
+    let mut site = Site::new(report);
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for input in &arguments.inputs {
+        watcher.watch(input, RecursiveMode::Recursive)?;
+    }
+
+    let is_input = |path: &Path| arguments.inputs.iter().any(|input| path.starts_with(input));
+
+    // The templates aren't necessarily part of the input. Their directories are watched rather
+    // than the files, which may not exist yet.
+    let mut site_files = vec![arguments.assets_file.clone()];
+    for template in [
+        &arguments.header_file,
+        &arguments.footer_file,
+        &arguments.head_file,
+    ] {
+        let (Some(directory), Some(name)) = (template.parent(), template.file_name()) else {
+            continue;
+        };
+        let Ok(directory) = directory.canonicalize() else {
+            continue;
+        };
+
+        if !is_input(&directory)
+            && !site_files
+                .iter()
+                .any(|file| file.parent() == Some(&directory))
+        {
+            watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+        }
+
+        site_files.push(directory.join(name));
+    }
+
+    let layouts = arguments
+        .layouts
+        .canonicalize()
+        .unwrap_or_else(|_| arguments.layouts.clone());
+    if layouts.is_dir() && !is_input(&layouts) {
+        watcher.watch(&layouts, RecursiveMode::NonRecursive)?;
+    }
+
+    let inputs: Vec<String> = arguments
+        .inputs
+        .iter()
+        .map(|input| describe(input))
+        .collect();
+    info!("Watching {} for changes...", inputs.join(", "));
+
+    let exclusions = Exclusions::new(&arguments.inputs, &arguments.exclude)?;
+    let mut templates = read_templates(arguments);
+    let renderer = create_renderer(arguments)?;
+
+    loop {
+        let mut changed = BTreeSet::new();
+        collect_event(receiver.recv()?, &mut changed);
+
+        loop {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(event) => collect_event(event, &mut changed),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        let start = Instant::now();
+
+        let rebuilt = match classify(arguments, &exclusions, &changed, &site_files, &layouts) {
+            Rebuild::Nothing => continue,
+            Rebuild::Site => {
+                templates = read_templates(arguments);
+                build_site(arguments, &mut site);
+
+                "site".to_owned()
+            }
+            // Every page lists the others, or may point to an asset whose fingerprint only a full
+            // build knows. A single file holds every page. The links to the pages written at
+            // their permalink are only known to a full build as well, and so are the pages
+            // including a partial.
+            Rebuild::Pages(pages)
+                if templates.lists_pages
+                    || arguments.fingerprint
+                    || arguments.single_file.is_some()
+                    || site.permalinks
+                    || pages.iter().any(|page| site.is_partial(page)) =>
+            {
+                build_site(arguments, &mut site);
+
+                "site".to_owned()
+            }
+            Rebuild::Pages(pages) => {
+                let mut rebuild_site = false;
+                for page in &pages {
+                    match process_file(arguments, page, &templates, &renderer) {
+                        Ok(Some(page)) => {
+                            rebuild_site |= page.data.metadata.contains_key("permalink")
+                                || site.has_new_partials(&page.data.includes)
+                        }
+                        Ok(None) => {}
+                        Err(error) => eprintln!("{}", error),
+                    }
+                }
+
+                // A page which was just given a permalink, or which includes another page.
+                if rebuild_site {
+                    build_site(arguments, &mut site);
+                }
+
+                match pages.as_slice() {
+                    [page] => describe(page),
+                    _ => format!("{} pages", pages.len()),
+                }
+            }
+        };
+
+        info!("rebuilt {} in {}ms", rebuilt, start.elapsed().as_millis());
+    }
+}